@@ -11,7 +11,6 @@ use crate::game_actions::GameActions;
 pub struct CameraSetup;
 
 pub struct CameraPlugin;
-//todo: camera movement should be independent of game logic clock
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
@@ -53,6 +52,27 @@ fn setup_camera(mut commands: Commands) {
             (GameActions::MoveRight, KeyCode::KeyD),
             (GameActions::ZoomIn, KeyCode::KeyQ),
             (GameActions::ZoomOut, KeyCode::KeyE),
+            (GameActions::TogglePause, KeyCode::Space),
+            (GameActions::IncreaseSpeed, KeyCode::Equal),
+            (GameActions::DecreaseSpeed, KeyCode::Minus),
+            (GameActions::AddToSelection, KeyCode::ShiftLeft),
+            (GameActions::ToggleSelection, KeyCode::ControlLeft),
+            (GameActions::SelectionGroup1, KeyCode::Digit1),
+            (GameActions::SelectionGroup2, KeyCode::Digit2),
+            (GameActions::SelectionGroup3, KeyCode::Digit3),
+            (GameActions::SelectionGroup4, KeyCode::Digit4),
+            (GameActions::SelectionGroup5, KeyCode::Digit5),
+            (GameActions::SelectionGroup6, KeyCode::Digit6),
+            (GameActions::SelectionGroup7, KeyCode::Digit7),
+            (GameActions::SelectionGroup8, KeyCode::Digit8),
+            (GameActions::SelectionGroup9, KeyCode::Digit9),
+            (GameActions::QueueOrder, KeyCode::ShiftLeft),
+            (GameActions::ClearOrders, KeyCode::Backspace),
+            (GameActions::FormConvoy, KeyCode::KeyC),
+            (GameActions::DisbandConvoy, KeyCode::KeyX),
+            (GameActions::SelectSuperior, KeyCode::KeyU),
+            (GameActions::DetachUnits, KeyCode::KeyV),
+            (GameActions::AttachUnits, KeyCode::KeyB),
         ])
         .with(GameActions::Select, MouseButton::Left),
     ));
@@ -61,7 +81,8 @@ fn setup_camera(mut commands: Commands) {
 fn update_camera(
     mut query: Single<(&mut Transform, &mut Projection, &ActionState<GameActions>), With<Camera2d>>,
     settings: Res<CameraSettings>,
-    time: Res<Time>,
+    // Real time, so the camera keeps moving while the game is paused.
+    time: Res<Time<Real>>,
 ) {
     let (transform, projection, input) = query.deref_mut();
     let delta = time.delta_secs();
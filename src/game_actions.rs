@@ -9,5 +9,31 @@ pub enum GameActions {
     MoveRight,
     ZoomIn,
     ZoomOut,
-    Select
-}
\ No newline at end of file
+    Select,
+    TogglePause,
+    IncreaseSpeed,
+    DecreaseSpeed,
+    AddToSelection,
+    /// Toggles units in and out of the selection; held together with a selection group key,
+    /// saves the current selection into that group.
+    ToggleSelection,
+    SelectionGroup1,
+    SelectionGroup2,
+    SelectionGroup3,
+    SelectionGroup4,
+    SelectionGroup5,
+    SelectionGroup6,
+    SelectionGroup7,
+    SelectionGroup8,
+    SelectionGroup9,
+    /// Held while issuing an order to append it to the unit's order queue.
+    QueueOrder,
+    ClearOrders,
+    FormConvoy,
+    DisbandConvoy,
+    /// Selects the composite units the selected units are attached to.
+    SelectSuperior,
+    DetachUnits,
+    /// Attaches the selected units to the selected composite unit of the highest echelon.
+    AttachUnits,
+}
@@ -13,12 +13,12 @@ use bevy_hui::HuiPlugin;
 use bevy_inspector_egui::{bevy_egui::EguiPlugin, quick::WorldInspectorPlugin};
 
 use crate::{
-    map::{HexGridPlugin, HexPosition},
-    movement::{MoveUnitMessage, MovementPlugin},
+    map::{HexGridPlugin, MapGeneratorSettings, MapSource},
+    movement::MovementPlugin,
     resources::ResourcesPlugin,
     time::GameTimePlugin,
     unit_managment::UnitManagementPlugin,
-    units::{AtomicUnitBundle, UnitPlugin},
+    units::UnitPlugin,
     user_interface::UserInterfacePlugin,
 };
 
@@ -29,19 +29,15 @@ pub enum GameState {
     Paused,
 }
 
-fn setup(mut commands: Commands, mut move_event: MessageWriter<MoveUnitMessage>) {
-    let unit = commands
-        .spawn(AtomicUnitBundle::new(
-            "Infantry".to_string(),
-            HexPosition::new(0, 0),
-        ))
-        .id();
-
-    // Send a movement event to the unit to move to (10, 5)
-    move_event.write(MoveUnitMessage {
-        unit,
-        destination: HexPosition { x: 10, y: 5 },
-    });
+/// `--seed <n>` plays on a map generated from the seed instead of the default map.
+fn map_source() -> MapSource {
+    std::env::args()
+        .skip_while(|arg| arg != "--seed")
+        .nth(1)
+        .and_then(|seed| seed.parse().ok())
+        .map_or_else(MapSource::default, |seed| {
+            MapSource::Generated(MapGeneratorSettings { seed, ..default() })
+        })
 }
 
 fn main() {
@@ -55,13 +51,13 @@ fn main() {
         .add_plugins(WorldInspectorPlugin::new())
         .add_plugins(MovementPlugin)
         .add_plugins(GameTimePlugin)
+        .insert_resource(map_source())
         .add_plugins(HexGridPlugin)
         .add_plugins(camera::CameraPlugin)
         .add_plugins(UnitPlugin)
         .add_plugins(UnitManagementPlugin)
         .add_plugins(UserInterfacePlugin)
         .add_plugins(ResourcesPlugin)
-        .add_systems(Startup, setup)
         .add_plugins(HuiPlugin)
         .insert_state(GameState::Running)
         .run();
@@ -0,0 +1,474 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    asset::{Asset, AssetLoader, LoadContext, io::Reader},
+    reflect::TypePath,
+};
+use hexx::{Hex, shapes};
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    map::{EdgeFeatures, HexEdge, RiverCrossing, RoadGrade},
+    movement::DifficultTerrain,
+    resources::{ResourceStackDefinition, ResourceTypes},
+    units::{Echelon, UnitTypeList, can_attach},
+};
+
+/// Hex coordinates as written in map files, `(x, y)`.
+type Coordinates = (i32, i32);
+
+fn hex_from_coordinates<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hex, D::Error> {
+    let (x, y) = Coordinates::deserialize(deserializer)?;
+    Ok(Hex::new(x, y))
+}
+
+fn hexes_from_coordinates<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Hex>, D::Error> {
+    let coordinates = Vec::<Coordinates>::deserialize(deserializer)?;
+    Ok(coordinates
+        .into_iter()
+        .map(|(x, y)| Hex::new(x, y))
+        .collect())
+}
+
+fn hexsides_from_coordinates<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Hexside>, D::Error> {
+    let pairs = Vec::<(Coordinates, Coordinates)>::deserialize(deserializer)?;
+    Ok(pairs
+        .into_iter()
+        .map(|((ax, ay), (bx, by))| [Hex::new(ax, ay), Hex::new(bx, by)])
+        .collect())
+}
+
+/// Border between two hexes, written as the pair of hexes, `((x, y), (x, y))`.
+pub type Hexside = [Hex; 2];
+
+/// A map ready to be turned into a [`HexGrid`](crate::map::HexGrid), either loaded from a
+/// `.map.ron` file or generated.
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct MapDefinition {
+    pub name: String,
+    /// The map is a hexagon of this radius around hex (0, 0).
+    pub radius: u32,
+    /// Terrain of every hex of the map.
+    pub terrain: HashMap<Hex, DifficultTerrain>,
+    pub roads: Vec<RoadDefinition>,
+    pub rivers: Vec<RiverDefinition>,
+    /// River hexsides which can be waded through.
+    pub fords: Vec<Hexside>,
+    /// River hexsides with a bridge but no road. Roads crossing a river always have a bridge.
+    pub bridges: Vec<Hexside>,
+    pub locations: Vec<LocationDefinition>,
+    pub factions: Vec<String>,
+    pub depots: Vec<DepotDefinition>,
+    pub formations: Vec<FormationDefinition>,
+    pub units: Vec<StartingUnitDefinition>,
+}
+
+impl MapDefinition {
+    pub fn contains(&self, hex: Hex) -> bool {
+        hex.ulength() <= self.radius
+    }
+
+    /// Features of every hex edge with a road or river. Hexsides between hexes which are not
+    /// adjacent are left out, [`validate_map`] rejects them.
+    pub fn edge_features(&self) -> HashMap<HexEdge, EdgeFeatures> {
+        let mut edges: HashMap<HexEdge, EdgeFeatures> = HashMap::new();
+        let edge = |[a, b]: Hexside| HexEdge::new(a, b);
+        for river in self.rivers.iter() {
+            for &hexside in river.hexsides.iter() {
+                if let Some(edge) = edge(hexside) {
+                    edges.entry(edge).or_default().river = Some(RiverCrossing::Impassable);
+                }
+            }
+        }
+        for (hexsides, crossing) in [
+            (&self.fords, RiverCrossing::Ford),
+            (&self.bridges, RiverCrossing::Bridge),
+        ] {
+            for &hexside in hexsides.iter() {
+                if let Some(features) = edge(hexside).and_then(|edge| edges.get_mut(&edge)) {
+                    features.river = Some(crossing);
+                }
+            }
+        }
+        for road in self.roads.iter() {
+            for pair in road.path.windows(2) {
+                let Some(edge) = edge([pair[0], pair[1]]) else {
+                    continue;
+                };
+                let features = edges.entry(edge).or_default();
+                features.road = features.road.max(Some(road.grade));
+                if features.river.is_some() {
+                    features.river = Some(RiverCrossing::Bridge);
+                }
+            }
+        }
+        edges
+    }
+}
+
+/// A road leading through the centres of a chain of adjacent hexes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoadDefinition {
+    #[serde(default)]
+    pub grade: RoadGrade,
+    #[serde(deserialize_with = "hexes_from_coordinates")]
+    pub path: Vec<Hex>,
+}
+
+/// A river running along hexsides. Rivers can only be crossed at fords and bridges.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RiverDefinition {
+    #[serde(deserialize_with = "hexsides_from_coordinates")]
+    pub hexsides: Vec<Hexside>,
+}
+
+/// A named place shown on the map, such as a town or a bridge.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocationDefinition {
+    pub name: String,
+    #[serde(deserialize_with = "hex_from_coordinates")]
+    pub hex: Hex,
+}
+
+/// A supply dump units can load from and unload into.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepotDefinition {
+    pub name: String,
+    #[serde(deserialize_with = "hex_from_coordinates")]
+    pub hex: Hex,
+    pub faction: String,
+    #[serde(default)]
+    pub supplies: Vec<ResourceStackDefinition>,
+}
+
+/// A composite unit, such as a platoon, other units can be attached to.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FormationDefinition {
+    pub name: String,
+    pub echelon: Echelon,
+    pub faction: String,
+    /// Name of the formation this one is part of.
+    #[serde(default)]
+    pub attached_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StartingUnitDefinition {
+    pub unit_type: String,
+    #[serde(deserialize_with = "hex_from_coordinates")]
+    pub hex: Hex,
+    pub faction: String,
+    /// Name of the formation the unit is part of.
+    #[serde(default)]
+    pub attached_to: Option<String>,
+}
+
+/// Terrain covering every hex at most `radius` away from `center`.
+#[derive(Debug, Clone, Deserialize)]
+struct AreaDefinition {
+    center: Coordinates,
+    radius: u32,
+    terrain: DifficultTerrain,
+}
+
+/// Contents of a `.map.ron` file. Terrain is filled in from the default, then areas, then
+/// single hexes, each overriding the previous. Roads and rivers run between hexes and leave
+/// the terrain alone.
+#[derive(Debug, Deserialize)]
+struct MapFile {
+    name: String,
+    radius: u32,
+    #[serde(default)]
+    default_terrain: DifficultTerrain,
+    #[serde(default)]
+    areas: Vec<AreaDefinition>,
+    #[serde(default)]
+    terrain: HashMap<Coordinates, DifficultTerrain>,
+    #[serde(default)]
+    roads: Vec<RoadDefinition>,
+    #[serde(default)]
+    rivers: Vec<RiverDefinition>,
+    #[serde(default, deserialize_with = "hexsides_from_coordinates")]
+    fords: Vec<Hexside>,
+    #[serde(default, deserialize_with = "hexsides_from_coordinates")]
+    bridges: Vec<Hexside>,
+    #[serde(default)]
+    locations: Vec<LocationDefinition>,
+    factions: Vec<String>,
+    #[serde(default)]
+    depots: Vec<DepotDefinition>,
+    #[serde(default)]
+    formations: Vec<FormationDefinition>,
+    #[serde(default)]
+    units: Vec<StartingUnitDefinition>,
+}
+
+impl MapFile {
+    fn into_definition(self) -> MapDefinition {
+        let to_hex = |(x, y): Coordinates| Hex::new(x, y);
+        let mut terrain: HashMap<Hex, DifficultTerrain> = shapes::hexagon(Hex::ZERO, self.radius)
+            .map(|hex| (hex, self.default_terrain))
+            .collect();
+        let mut paint = |hex: Hex, kind: DifficultTerrain| {
+            if let Some(current) = terrain.get_mut(&hex) {
+                *current = kind;
+            }
+        };
+        for area in self.areas.iter() {
+            for hex in to_hex(area.center).range(area.radius) {
+                paint(hex, area.terrain);
+            }
+        }
+        for (&coordinates, &kind) in self.terrain.iter() {
+            paint(to_hex(coordinates), kind);
+        }
+        MapDefinition {
+            name: self.name,
+            radius: self.radius,
+            terrain,
+            roads: self.roads,
+            rivers: self.rivers,
+            fords: self.fords,
+            bridges: self.bridges,
+            locations: self.locations,
+            factions: self.factions,
+            depots: self.depots,
+            formations: self.formations,
+            units: self.units,
+        }
+    }
+}
+
+/// Whether two hexsides meet at a corner. The three hexes around a corner are mutually
+/// adjacent, so the hexsides share one hex and their other hexes are neighbours.
+fn share_corner([a, b]: Hexside, [c, d]: Hexside) -> bool {
+    let (first, second) = if a == c {
+        (b, d)
+    } else if a == d {
+        (b, c)
+    } else if b == c {
+        (a, d)
+    } else if b == d {
+        (a, c)
+    } else {
+        return false;
+    };
+    first.unsigned_distance_to(second) == 1
+}
+
+/// Checks that everything on the map is inside it and every name refers to something defined.
+pub fn validate_map(map: &MapDefinition) -> Result<(), String> {
+    if map.radius == 0 {
+        return Err("radius must be at least 1".to_string());
+    }
+    let inside = |hex: Hex, what: &str| {
+        if map.contains(hex) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{what} at ({}, {}) is outside of the map",
+                hex.x, hex.y
+            ))
+        }
+    };
+    let hexside = |[a, b]: Hexside, what: &str| {
+        inside(a, what)?;
+        inside(b, what)?;
+        if a.unsigned_distance_to(b) == 1 {
+            Ok(())
+        } else {
+            Err(format!(
+                "{what} runs between ({}, {}) and ({}, {}), which are not adjacent",
+                a.x, a.y, b.x, b.y
+            ))
+        }
+    };
+    for road in map.roads.iter() {
+        if road.path.len() < 2 {
+            return Err("road must span at least two hexes".to_string());
+        }
+        for pair in road.path.windows(2) {
+            hexside([pair[0], pair[1]], "road")?;
+        }
+    }
+    let mut river_edges = HashSet::new();
+    for river in map.rivers.iter() {
+        for &side in river.hexsides.iter() {
+            hexside(side, "river")?;
+            river_edges.extend(HexEdge::new(side[0], side[1]));
+        }
+        for pair in river.hexsides.windows(2) {
+            if !share_corner(pair[0], pair[1]) {
+                let [a, b] = pair[1];
+                return Err(format!(
+                    "river breaks off before hexside between ({}, {}) and ({}, {})",
+                    a.x, a.y, b.x, b.y
+                ));
+            }
+        }
+    }
+    for (kind, crossings) in [("ford", &map.fords), ("bridge", &map.bridges)] {
+        for &side in crossings.iter() {
+            hexside(side, kind)?;
+            if !HexEdge::new(side[0], side[1]).is_some_and(|edge| river_edges.contains(&edge)) {
+                return Err(format!(
+                    "{kind} between ({}, {}) and ({}, {}) is not on a river",
+                    side[0].x, side[0].y, side[1].x, side[1].y
+                ));
+            }
+        }
+    }
+    for location in map.locations.iter() {
+        inside(location.hex, &format!("location {:?}", location.name))?;
+    }
+
+    let mut factions = HashSet::new();
+    for faction in map.factions.iter() {
+        if !factions.insert(faction.as_str()) {
+            return Err(format!("faction {faction:?} is defined twice"));
+        }
+    }
+    let known_faction = |faction: &str, what: &str| {
+        if factions.contains(faction) {
+            Ok(())
+        } else {
+            Err(format!("{what} belongs to unknown faction {faction:?}"))
+        }
+    };
+    for depot in map.depots.iter() {
+        let what = format!("depot {:?}", depot.name);
+        inside(depot.hex, &what)?;
+        known_faction(&depot.faction, &what)?;
+    }
+
+    let mut formations = HashMap::new();
+    for formation in map.formations.iter() {
+        if formations
+            .insert(formation.name.as_str(), formation)
+            .is_some()
+        {
+            return Err(format!("formation {:?} is defined twice", formation.name));
+        }
+    }
+    for formation in map.formations.iter() {
+        let what = format!("formation {:?}", formation.name);
+        known_faction(&formation.faction, &what)?;
+        let Some(superior_name) = &formation.attached_to else {
+            continue;
+        };
+        let superior = formations
+            .get(superior_name.as_str())
+            .ok_or_else(|| format!("{what} is attached to unknown formation {superior_name:?}"))?;
+        if !can_attach(formation.echelon, superior.echelon) {
+            return Err(format!(
+                "{what} ({:?}) cannot be attached to {superior_name:?} ({:?})",
+                formation.echelon, superior.echelon
+            ));
+        }
+    }
+    for unit in map.units.iter() {
+        let what = format!("unit {:?}", unit.unit_type);
+        inside(unit.hex, &what)?;
+        known_faction(&unit.faction, &what)?;
+        if let Some(formation) = &unit.attached_to
+            && !formations.contains_key(formation.as_str())
+        {
+            return Err(format!(
+                "{what} at ({}, {}) is attached to unknown formation {formation:?}",
+                unit.hex.x, unit.hex.y
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the parts of a map which refer to the unit and resource catalogs, which are only
+/// available once those have loaded too. Expects a map that passed [`validate_map`].
+pub fn validate_scenario(
+    map: &MapDefinition,
+    unit_types: &UnitTypeList,
+    resource_types: &ResourceTypes,
+) -> Result<(), String> {
+    for depot in map.depots.iter() {
+        for supply in depot.supplies.iter() {
+            supply
+                .resolve(resource_types)
+                .map_err(|error| format!("depot {:?} holds {error}", depot.name))?;
+        }
+    }
+    for unit in map.units.iter() {
+        let what = format!("unit at ({}, {})", unit.hex.x, unit.hex.y);
+        let details = unit_types
+            .types
+            .get(&unit.unit_type)
+            .ok_or_else(|| format!("{what} has unknown type {:?}", unit.unit_type))?;
+        let Some(formation_name) = &unit.attached_to else {
+            continue;
+        };
+        let formation = map
+            .formations
+            .iter()
+            .find(|formation| &formation.name == formation_name)
+            .ok_or_else(|| format!("{what} is attached to unknown formation {formation_name:?}"))?;
+        if !can_attach(details.echelon, formation.echelon) {
+            return Err(format!(
+                "{what} ({:?}, {:?}) cannot be attached to {formation_name:?} ({:?})",
+                unit.unit_type, details.echelon, formation.echelon
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum MapDefinitionError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl std::fmt::Display for MapDefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapDefinitionError::Io(error) => write!(f, "could not read map: {error}"),
+            MapDefinitionError::Parse(error) => write!(f, "could not parse map: {error}"),
+            MapDefinitionError::Invalid(reason) => write!(f, "map is invalid: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for MapDefinitionError {}
+
+#[derive(Default)]
+pub struct MapLoader;
+
+impl AssetLoader for MapLoader {
+    type Asset = MapDefinition;
+    type Settings = ();
+    type Error = MapDefinitionError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(MapDefinitionError::Io)?;
+        let file: MapFile = ron::de::from_bytes(&bytes).map_err(MapDefinitionError::Parse)?;
+        let map = file.into_definition();
+        validate_map(&map).map_err(MapDefinitionError::Invalid)?;
+        Ok(map)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["map.ron"]
+    }
+}
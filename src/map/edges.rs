@@ -0,0 +1,158 @@
+use bevy::{
+    color::Color,
+    ecs::{component::Component, entity::Entity, name::Name, system::Commands},
+    math::{Quat, Vec2},
+    reflect::Reflect,
+    sprite::Sprite,
+    transform::components::Transform,
+    utils::default,
+};
+use hexx::Hex;
+use serde::Deserialize;
+
+use crate::map::{HexGrid, SPRITE_SIZE};
+
+const ROAD_COLOR: Color = Color::srgb(0.45, 0.35, 0.25);
+const RIVER_COLOR: Color = Color::srgb(0.2, 0.45, 0.85);
+const FORD_COLOR: Color = Color::srgb(0.45, 0.7, 0.95);
+const BRIDGE_COLOR: Color = Color::srgb(0.3, 0.3, 0.3);
+const RIVER_WIDTH: f32 = 3.0;
+const BRIDGE_WIDTH: f32 = 6.0;
+/// Pointy hexes have sides as long as their circumradius.
+const HEX_SIDE: f32 = SPRITE_SIZE.y / 2.0;
+/// Above the hex tiles, below the hover highlight.
+const RIVER_Z: f32 = 0.1;
+const ROAD_Z: f32 = 0.15;
+const BRIDGE_Z: f32 = 0.2;
+
+/// Border between two adjacent hexes, the same whichever side it is seen from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HexEdge([Hex; 2]);
+
+impl HexEdge {
+    /// The edge between two hexes, `None` if they are not adjacent.
+    pub fn new(a: Hex, b: Hex) -> Option<Self> {
+        if a.unsigned_distance_to(b) != 1 {
+            return None;
+        }
+        Some(if (a.x, a.y) < (b.x, b.y) {
+            Self([a, b])
+        } else {
+            Self([b, a])
+        })
+    }
+
+    pub fn hexes(&self) -> [Hex; 2] {
+        self.0
+    }
+}
+
+/// Quality of a road, better roads are faster to travel.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Reflect, Deserialize,
+)]
+pub enum RoadGrade {
+    Track,
+    #[default]
+    Road,
+    Highway,
+}
+
+impl RoadGrade {
+    /// Multiplier on the unit's own road penalty.
+    pub fn penalty(&self) -> f32 {
+        match self {
+            RoadGrade::Track => 1.3,
+            RoadGrade::Road => 1.0,
+            RoadGrade::Highway => 0.8,
+        }
+    }
+}
+
+/// How a river running along an edge can be crossed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Deserialize)]
+pub enum RiverCrossing {
+    /// The river cannot be crossed here.
+    Impassable,
+    /// Units which can wade through rivers cross, slowed down as in river terrain.
+    Ford,
+    /// Units cross as if there was no river.
+    Bridge,
+}
+
+/// Features of a single hex edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub struct EdgeFeatures {
+    /// Road leading across the edge, from one hex centre to the other.
+    pub road: Option<RoadGrade>,
+    /// River running along the edge.
+    pub river: Option<RiverCrossing>,
+}
+
+/// Sprite drawing a road, river or bridge on the map.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+pub struct EdgeOverlay;
+
+/// A thin sprite from `start` to `end`.
+fn segment(start: Vec2, end: Vec2, width: f32, color: Color, z: f32) -> (Sprite, Transform) {
+    let direction = end - start;
+    let center = (start + end) / 2.0;
+    (
+        Sprite {
+            custom_size: Some(Vec2::new(direction.length(), width)),
+            color,
+            ..default()
+        },
+        Transform::from_translation(center.extend(z))
+            .with_rotation(Quat::from_rotation_z(direction.to_angle())),
+    )
+}
+
+/// Spawns the overlays of every road, river and bridge as children of `parent`.
+pub fn spawn_edge_overlays(commands: &mut Commands, grid: &HexGrid, parent: Entity) {
+    for (edge, features) in grid.edges.iter() {
+        let [a, b] = edge.hexes().map(|hex| grid.to_global_coordinates(hex));
+        let across = b - a;
+        // The border is perpendicular to the line between the two hex centres.
+        let along = across.perp().normalize_or_zero() * HEX_SIDE / 2.0;
+        let middle = (a + b) / 2.0;
+        let mut overlays = Vec::new();
+        if let Some(crossing) = features.river {
+            let color = match crossing {
+                RiverCrossing::Ford => FORD_COLOR,
+                RiverCrossing::Impassable | RiverCrossing::Bridge => RIVER_COLOR,
+            };
+            overlays.push(segment(
+                middle - along,
+                middle + along,
+                RIVER_WIDTH,
+                color,
+                RIVER_Z,
+            ));
+            if crossing == RiverCrossing::Bridge {
+                let span = across.normalize_or_zero() * BRIDGE_WIDTH;
+                overlays.push(segment(
+                    middle - span,
+                    middle + span,
+                    BRIDGE_WIDTH,
+                    BRIDGE_COLOR,
+                    BRIDGE_Z,
+                ));
+            }
+        }
+        if let Some(grade) = features.road {
+            let width = match grade {
+                RoadGrade::Track => 1.5,
+                RoadGrade::Road => 2.5,
+                RoadGrade::Highway => 3.5,
+            };
+            overlays.push(segment(a, b, width, ROAD_COLOR, ROAD_Z));
+        }
+        for overlay in overlays {
+            let entity = commands
+                .spawn((Name::new("Edge Overlay"), EdgeOverlay, overlay))
+                .id();
+            commands.entity(parent).add_child(entity);
+        }
+    }
+}
@@ -0,0 +1,529 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{math::Vec2, reflect::Reflect};
+use hexx::{Hex, algorithms::a_star, shapes};
+
+use crate::{
+    map::{
+        HexEdge, MapDefinition, RoadGrade,
+        definition::{
+            DepotDefinition, FormationDefinition, Hexside, LocationDefinition, RiverDefinition,
+            RoadDefinition, StartingUnitDefinition,
+        },
+    },
+    movement::DifficultTerrain,
+    resources::{ResourceAmout, ResourceStackDefinition},
+    units::Echelon,
+};
+
+/// Noise features span roughly this many hexes.
+const NOISE_SCALE: f32 = 12.0;
+const NOISE_OCTAVES: u32 = 4;
+/// Share of the highest hexes rivers may spring from.
+const RIVER_SOURCE_SHARE: f32 = 0.2;
+/// Shorter rivers are dropped, they end in a sink too close to their source.
+const MIN_RIVER_LENGTH: usize = 4;
+const PLACEMENT_ATTEMPTS: usize = 50;
+const FACTIONS: [&str; 2] = ["Blue", "Red"];
+
+/// Parameters of a generated map. The same settings always yield the same map.
+#[derive(Debug, Clone, Reflect)]
+pub struct MapGeneratorSettings {
+    pub seed: u64,
+    pub radius: u32,
+    /// Share of hexes, from the highest, which are mountains.
+    pub mountain_share: f32,
+    /// Share of hexes, from the lowest, which are mud.
+    pub mud_share: f32,
+    /// Share of hexes, from the wettest, which are forest.
+    pub forest_share: f32,
+    pub rivers: usize,
+    pub towns: usize,
+}
+
+impl Default for MapGeneratorSettings {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            radius: 40,
+            mountain_share: 0.08,
+            mud_share: 0.06,
+            forest_share: 0.25,
+            rivers: 3,
+            towns: 6,
+        }
+    }
+}
+
+/// SplitMix64, small and good enough for map generation. Map generation must not depend on
+/// anything but the seed, so no thread or OS randomness is used.
+struct MapRng(u64);
+
+impl MapRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed in `0..n`, `n` must not be zero.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Pseudo random value in `0..1` attached to a lattice point.
+fn lattice_value(seed: u64, x: i64, y: i64) -> f32 {
+    let mut rng = MapRng(
+        seed ^ (x as u64).wrapping_mul(0x6C8E_9CF5_7093_2BD5)
+            ^ (y as u64).wrapping_mul(0xD6E8_FEB8_6659_FD93),
+    );
+    (rng.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Value noise in `0..1`, smoothly interpolated between lattice points.
+fn value_noise(seed: u64, point: Vec2) -> f32 {
+    let cell = point.floor();
+    let t = point - cell;
+    let t = t * t * (Vec2::splat(3.0) - 2.0 * t);
+    let (x, y) = (cell.x as i64, cell.y as i64);
+    let top = lattice_value(seed, x, y) * (1.0 - t.x) + lattice_value(seed, x + 1, y) * t.x;
+    let bottom =
+        lattice_value(seed, x, y + 1) * (1.0 - t.x) + lattice_value(seed, x + 1, y + 1) * t.x;
+    top * (1.0 - t.y) + bottom * t.y
+}
+
+/// Several octaves of value noise over a hex, in `0..1`.
+fn fractal_noise(seed: u64, hex: Hex) -> f32 {
+    // Pointy hex centres, so features are not stretched along an axis.
+    let point = Vec2::new(
+        3f32.sqrt() * (hex.x as f32 + hex.y as f32 / 2.0),
+        1.5 * hex.y as f32,
+    ) / NOISE_SCALE;
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut amplitudes = 0.0;
+    for octave in 0..NOISE_OCTAVES {
+        let frequency = (1 << octave) as f32;
+        total += amplitude * value_noise(seed.wrapping_add(octave as u64), point * frequency);
+        amplitudes += amplitude;
+        amplitude /= 2.0;
+    }
+    total / amplitudes
+}
+
+/// Value `share` of the given values lie above.
+fn upper_quantile(values: &[f32], share: f32) -> f32 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let index = ((1.0 - share.clamp(0.0, 1.0)) * sorted.len() as f32) as usize;
+    sorted[index.min(sorted.len() - 1)]
+}
+
+/// Generates a map from noise based elevation and moisture. Rivers flow downhill from high
+/// ground, towns are connected by roads and each faction starts next to a town of its own.
+pub fn generate_map(settings: &MapGeneratorSettings) -> MapDefinition {
+    let mut rng = MapRng(settings.seed);
+    let radius = settings.radius.max(1);
+    // Hashed containers are only used for lookups, anything the result depends on iterates
+    // over `hexes` so its order is the same every time.
+    let hexes: Vec<Hex> = shapes::hexagon(Hex::ZERO, radius).collect();
+    let contains = |hex: Hex| hex.ulength() <= radius;
+
+    let elevation_seed = rng.next_u64();
+    let moisture_seed = rng.next_u64();
+    let elevation: HashMap<Hex, f32> = hexes
+        .iter()
+        .map(|&hex| (hex, fractal_noise(elevation_seed, hex)))
+        .collect();
+    let heights: Vec<f32> = hexes.iter().map(|hex| elevation[hex]).collect();
+    let mountain_level = upper_quantile(&heights, settings.mountain_share);
+    let mud_level = upper_quantile(&heights, 1.0 - settings.mud_share);
+    let moisture: Vec<f32> = hexes
+        .iter()
+        .map(|&hex| fractal_noise(moisture_seed, hex))
+        .collect();
+    let forest_level = upper_quantile(&moisture, settings.forest_share);
+
+    let terrain: HashMap<Hex, DifficultTerrain> = hexes
+        .iter()
+        .zip(moisture.iter())
+        .map(|(&hex, &wetness)| {
+            let height = elevation[&hex];
+            let kind = if height > mountain_level {
+                DifficultTerrain::Mountain
+            } else if height < mud_level {
+                DifficultTerrain::Mud
+            } else if wetness > forest_level {
+                DifficultTerrain::Forest
+            } else {
+                DifficultTerrain::Open
+            };
+            (hex, kind)
+        })
+        .collect();
+
+    let rivers = trace_rivers(settings, radius, &hexes, &elevation, &mut rng);
+    let river_edges: HashSet<HexEdge> = rivers
+        .iter()
+        .flat_map(|river| river.hexsides.iter())
+        .filter_map(|&[a, b]| HexEdge::new(a, b))
+        .collect();
+    let fords = rivers
+        .iter()
+        .map(|river| river.hexsides[rng.below(river.hexsides.len())])
+        .collect();
+
+    let towns = place_towns(settings, &hexes, &terrain, &mut rng);
+    let roads = connect_towns(&towns, &terrain, &river_edges, contains);
+
+    let mut map = MapDefinition {
+        name: format!("Generated ({})", settings.seed),
+        radius,
+        terrain,
+        roads,
+        rivers,
+        fords,
+        bridges: Vec::new(),
+        locations: towns
+            .iter()
+            .enumerate()
+            .map(|(index, &hex)| LocationDefinition {
+                name: town_name(&mut rng, index),
+                hex,
+            })
+            .collect(),
+        factions: FACTIONS.iter().map(|faction| faction.to_string()).collect(),
+        depots: Vec::new(),
+        formations: Vec::new(),
+        units: Vec::new(),
+    };
+    place_factions(&mut map, &towns);
+    map
+}
+
+/// Corner shared by three hexes. The hexes are sorted, so each corner has a single
+/// representation.
+type Vertex = [Hex; 3];
+
+fn vertex(mut hexes: [Hex; 3]) -> Vertex {
+    hexes.sort_by_key(|hex| (hex.x, hex.y));
+    hexes
+}
+
+/// Corners at the far ends of the three hexsides meeting at `corner`, with those hexsides.
+fn adjacent_vertices(corner: Vertex) -> [(Vertex, Hexside); 3] {
+    [(0, 1, 2), (0, 2, 1), (1, 2, 0)].map(|(i, j, k)| {
+        let (a, b, c) = (corner[i], corner[j], corner[k]);
+        // Two adjacent hexes have two neighbours in common, one on each end of their hexside.
+        let far = a
+            .all_neighbors()
+            .into_iter()
+            .find(|&hex| hex != c && hex.unsigned_distance_to(b) == 1)
+            .expect("adjacent hexes share two neighbours");
+        (vertex([a, b, far]), [a, b])
+    })
+}
+
+/// Rivers start on high ground and run along hexsides, following the steepest descent until
+/// they leave the map, run into another river or reach a sink.
+fn trace_rivers(
+    settings: &MapGeneratorSettings,
+    radius: u32,
+    hexes: &[Hex],
+    elevation: &HashMap<Hex, f32>,
+    rng: &mut MapRng,
+) -> Vec<RiverDefinition> {
+    let heights: Vec<f32> = hexes.iter().map(|hex| elevation[hex]).collect();
+    let source_level = upper_quantile(&heights, RIVER_SOURCE_SHARE);
+    let sources: Vec<Hex> = hexes
+        .iter()
+        .copied()
+        .filter(|hex| elevation[hex] >= source_level)
+        .collect();
+    // Corners get the average elevation of their hexes, corners on the map border have none.
+    let height = |corner: &Vertex| -> Option<f32> {
+        if corner.iter().any(|hex| hex.ulength() >= radius) {
+            return None;
+        }
+        Some(corner.iter().map(|hex| elevation[hex]).sum::<f32>() / 3.0)
+    };
+    let mut rivers = Vec::new();
+    let mut river_vertices = HashSet::new();
+    if sources.is_empty() {
+        return rivers;
+    }
+    for _ in 0..settings.rivers * PLACEMENT_ATTEMPTS {
+        if rivers.len() == settings.rivers {
+            break;
+        }
+        let source = sources[rng.below(sources.len())];
+        let [first, second, ..] = source.all_neighbors();
+        let mut current = vertex([source, first, second]);
+        let Some(mut current_height) = height(&current) else {
+            continue;
+        };
+        if river_vertices.contains(&current) {
+            continue;
+        }
+        let mut course = vec![current];
+        let mut hexsides = Vec::new();
+        loop {
+            let lowest = adjacent_vertices(current)
+                .into_iter()
+                .map(|(corner, hexside)| (height(&corner), corner, hexside))
+                .min_by(|a, b| {
+                    // Corners on the border count as lowest, the river flows off the map.
+                    let key = |height: Option<f32>| height.unwrap_or(f32::NEG_INFINITY);
+                    key(a.0).total_cmp(&key(b.0))
+                });
+            let Some((next_height, next, hexside)) = lowest else {
+                break;
+            };
+            if next_height.is_some_and(|next_height| next_height >= current_height) {
+                break;
+            }
+            hexsides.push(hexside);
+            course.push(next);
+            let Some(next_height) = next_height else {
+                break;
+            };
+            if river_vertices.contains(&next) {
+                break;
+            }
+            current = next;
+            current_height = next_height;
+        }
+        if hexsides.len() >= MIN_RIVER_LENGTH {
+            river_vertices.extend(course);
+            rivers.push(RiverDefinition { hexsides });
+        }
+    }
+    rivers
+}
+
+/// Towns go on open ground, spread out over the map.
+fn place_towns(
+    settings: &MapGeneratorSettings,
+    hexes: &[Hex],
+    terrain: &HashMap<Hex, DifficultTerrain>,
+    rng: &mut MapRng,
+) -> Vec<Hex> {
+    let spacing = (settings.radius / 3).max(3);
+    let candidates: Vec<Hex> = hexes
+        .iter()
+        .copied()
+        .filter(|hex| terrain[hex] == DifficultTerrain::Open)
+        .collect();
+    let mut towns: Vec<Hex> = Vec::new();
+    if candidates.is_empty() {
+        return towns;
+    }
+    for _ in 0..settings.towns * PLACEMENT_ATTEMPTS {
+        if towns.len() == settings.towns {
+            break;
+        }
+        let candidate = candidates[rng.below(candidates.len())];
+        if towns
+            .iter()
+            .all(|town| town.unsigned_distance_to(candidate) >= spacing)
+        {
+            towns.push(candidate);
+        }
+    }
+    towns
+}
+
+/// Cost of building a road into a hex. Existing roads are reused, rivers need a bridge.
+fn road_cost(terrain: DifficultTerrain) -> u32 {
+    match terrain {
+        DifficultTerrain::Road => 1,
+        DifficultTerrain::Open => 3,
+        DifficultTerrain::Forest | DifficultTerrain::Mud => 6,
+        DifficultTerrain::River => 10,
+        DifficultTerrain::Mountain => 20,
+    }
+}
+
+const BRIDGE_COST: u32 = 10;
+
+/// Links all towns with the shortest roads between them, nearest towns first.
+fn connect_towns(
+    towns: &[Hex],
+    terrain: &HashMap<Hex, DifficultTerrain>,
+    river_edges: &HashSet<HexEdge>,
+    contains: impl Fn(Hex) -> bool,
+) -> Vec<RoadDefinition> {
+    let mut roads = Vec::new();
+    let mut road_edges = HashSet::new();
+    let Some((&first, _)) = towns.split_first() else {
+        return roads;
+    };
+    let mut connected = vec![first];
+    let mut remaining: Vec<Hex> = towns[1..].to_vec();
+    while !remaining.is_empty() {
+        let (index, from) = remaining
+            .iter()
+            .enumerate()
+            .flat_map(|(index, &town)| connected.iter().map(move |&from| (index, from, town)))
+            .min_by_key(|&(_, from, town)| from.unsigned_distance_to(town))
+            .map(|(index, from, _)| (index, from))
+            .expect("remaining and connected towns are not empty");
+        let town = remaining.remove(index);
+        let road = a_star(from, town, |current, next| {
+            if !contains(next) {
+                return None;
+            }
+            let edge = HexEdge::new(current, next)?;
+            if road_edges.contains(&edge) {
+                return Some(1);
+            }
+            let bridge = if river_edges.contains(&edge) {
+                BRIDGE_COST
+            } else {
+                0
+            };
+            Some(road_cost(terrain[&next]) + bridge)
+        });
+        if let Some(path) = road {
+            road_edges.extend(
+                path.windows(2)
+                    .filter_map(|pair| HexEdge::new(pair[0], pair[1])),
+            );
+            roads.push(RoadDefinition {
+                grade: RoadGrade::Road,
+                path,
+            });
+        }
+        connected.push(town);
+    }
+    roads
+}
+
+fn town_name(rng: &mut MapRng, index: usize) -> String {
+    const PREFIXES: [&str; 12] = [
+        "Ash", "Bel", "Cor", "Dun", "Fen", "Gar", "Hal", "Kir", "Mar", "Nor", "Ros", "Wen",
+    ];
+    const SUFFIXES: [&str; 8] = [
+        "ford", "ton", "burg", "ley", "wick", "dale", "mont", "stead",
+    ];
+    let prefix = PREFIXES[rng.below(PREFIXES.len())];
+    let suffix = SUFFIXES[rng.below(SUFFIXES.len())];
+    // Names may repeat, the index keeps them apart.
+    format!("{prefix}{suffix} {}", index + 1)
+}
+
+/// Gives each faction a depot and a platoon with a truck at a town, the two towns as far
+/// apart as possible.
+fn place_factions(map: &mut MapDefinition, towns: &[Hex]) {
+    let Some(&blue_home) = towns.first() else {
+        return;
+    };
+    let red_home = towns
+        .iter()
+        .copied()
+        .max_by_key(|town| town.unsigned_distance_to(blue_home))
+        .filter(|&town| town != blue_home);
+    for (faction, home) in FACTIONS.into_iter().zip([Some(blue_home), red_home]) {
+        let Some(home) = home else {
+            continue;
+        };
+        let platoon = format!("{faction} 1st Platoon");
+        map.formations.push(FormationDefinition {
+            name: platoon.clone(),
+            echelon: Echelon::Platoon,
+            faction: faction.to_string(),
+            attached_to: None,
+        });
+        map.depots.push(DepotDefinition {
+            name: format!("{faction} Depot"),
+            hex: home,
+            faction: faction.to_string(),
+            supplies: [
+                ("rations", 2000.0),
+                ("fuel", 4000.0),
+                ("small_arms_ammo", 500.0),
+            ]
+            .into_iter()
+            .map(|(resource, count)| ResourceStackDefinition {
+                resource: resource.into(),
+                amount: ResourceAmout::Fluid { count },
+            })
+            .collect(),
+        });
+        map.units.push(StartingUnitDefinition {
+            unit_type: "Light Truck".to_string(),
+            hex: home,
+            faction: faction.to_string(),
+            attached_to: None,
+        });
+        let squad_hexes = home
+            .spiral_range(1..=2)
+            .filter(|hex| {
+                map.terrain.get(hex).is_some_and(|terrain| {
+                    !matches!(
+                        terrain,
+                        DifficultTerrain::Mountain | DifficultTerrain::River
+                    )
+                })
+            })
+            .take(3);
+        for hex in squad_hexes.collect::<Vec<_>>() {
+            map.units.push(StartingUnitDefinition {
+                unit_type: "Infantry".to_string(),
+                hex,
+                faction: faction.to_string(),
+                attached_to: Some(platoon.clone()),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::definition::validate_map;
+
+    fn generate(seed: u64) -> MapDefinition {
+        generate_map(&MapGeneratorSettings {
+            seed,
+            ..MapGeneratorSettings::default()
+        })
+    }
+
+    fn roads(map: &MapDefinition) -> Vec<(RoadGrade, Vec<Hex>)> {
+        map.roads
+            .iter()
+            .map(|road| (road.grade, road.path.clone()))
+            .collect()
+    }
+
+    fn rivers(map: &MapDefinition) -> Vec<Vec<Hexside>> {
+        map.rivers
+            .iter()
+            .map(|river| river.hexsides.clone())
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_yields_same_map() {
+        let first = generate(42);
+        let second = generate(42);
+        assert_eq!(first.terrain, second.terrain);
+        assert_eq!(roads(&first), roads(&second));
+        assert_eq!(rivers(&first), rivers(&second));
+        assert_eq!(first.fords, second.fords);
+        assert_eq!(validate_map(&first), Ok(()));
+    }
+
+    #[test]
+    fn different_seeds_yield_different_maps() {
+        let first = generate(1);
+        let second = generate(2);
+        assert_ne!(first.terrain, second.terrain);
+        assert_eq!(validate_map(&first), Ok(()));
+        assert_eq!(validate_map(&second), Ok(()));
+    }
+}
@@ -0,0 +1,593 @@
+mod definition;
+mod edges;
+mod generator;
+mod scenario;
+use std::{cmp::Reverse, collections::BinaryHeap, time::Duration};
+
+use bevy::{
+    app::{App, Plugin, Startup},
+    asset::{AssetApp, AssetServer, Assets, Handle},
+    ecs::{
+        entity::Entity,
+        resource::Resource,
+        system::{Commands, Local, Res, ResMut},
+    },
+    image::{TextureAtlas, TextureAtlasLayout},
+    math::uvec2,
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+    sprite::Sprite,
+    transform::components::Transform,
+    utils::default,
+};
+use hexx::*;
+
+use crate::{
+    map::{
+        definition::{MapLoader, validate_map, validate_scenario},
+        edges::spawn_edge_overlays,
+        scenario::spawn_scenario,
+    },
+    movement::{
+        BASE_STEP_COST, DifficultTerrain, GamePosition, Kph, MovementMode, MovementStats,
+        MovingTowards, PROGRESS_COMPLETE, hex_crossing_time,
+    },
+    resources::ResourceTypes,
+    units::{HexOccupancy, UnitTypeList},
+};
+
+pub use definition::MapDefinition;
+pub use edges::{EdgeFeatures, EdgeOverlay, HexEdge, RiverCrossing, RoadGrade};
+pub use generator::{MapGeneratorSettings, generate_map};
+pub use scenario::{Depot, MapLocation};
+
+pub const MAP_PATH: &str = "maps/skirmish.map.ron";
+
+pub const SPRITE_SIZE: Vec2 = Vec2::new(24.0, 28.0);
+pub const HEX_RADIUS_IN_METERS: f32 = 100.0;
+/// Most expensive route `find_path` searches for, in path finding units.
+pub const MAX_PATH_COST: u32 = BASE_STEP_COST as u32 * 2000;
+/// Multiplier on the cost of moving through a hex already at its stacking limit.
+const FULL_HEX_COST_FACTOR: u32 = 4;
+
+pub type HexPosition = Hex;
+
+/// Marks the sprite entity representing a single hex of the grid.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+pub struct HexTile {
+    pub hex: Hex,
+}
+
+#[derive(Debug, Resource)]
+pub struct HexGrid {
+    entities: HashMap<Hex, Entity>,
+    /// Roads and rivers, keyed by the edge they are on.
+    edges: HashMap<HexEdge, EdgeFeatures>,
+    layout: HexLayout,
+}
+
+impl HexGrid {
+    pub fn entity_at(&self, hex: Hex) -> Option<Entity> {
+        self.entities.get(&hex).cloned()
+    }
+
+    /// Roads and rivers on the edge between two adjacent hexes.
+    pub fn edge_between(&self, a: Hex, b: Hex) -> Option<&EdgeFeatures> {
+        HexEdge::new(a, b).and_then(|edge| self.edges.get(&edge))
+    }
+
+    pub fn to_global_coordinates(&self, hex: Hex) -> Vec2 {
+        self.layout.hex_to_world_pos(hex)
+    }
+
+    pub fn to_hex_coordinates(&self, pos: Vec2) -> Hex {
+        self.layout.world_pos_to_hex(pos)
+    }
+
+    pub fn terrain_at(
+        &self,
+        hex: Hex,
+        terrain: &Query<&DifficultTerrain>,
+    ) -> Option<DifficultTerrain> {
+        self.entity_at(hex)
+            .and_then(|entity| terrain.get(entity).ok())
+            .copied()
+    }
+
+    /// Finds the cheapest route for a unit with given stats. Each step costs according to the
+    /// terrain of the hex being entered and any road or river on the edge crossed; hexes
+    /// outside the grid, terrain the unit cannot enter and rivers without a crossing are never
+    /// part of the route. The unit may pass through hexes without room for
+    /// its `stacking_value` at a higher cost, but cannot end its move in one.
+    pub fn find_path(
+        &self,
+        start: Hex,
+        end: Hex,
+        stats: &MovementStats,
+        terrain: &Query<&DifficultTerrain>,
+        occupancy: &HexOccupancy,
+        stacking_value: f32,
+    ) -> Result<Vec<Hex>, PathFindingError> {
+        let destination_terrain = self
+            .terrain_at(end, terrain)
+            .ok_or(PathFindingError::OutOfBounds { hex: end })?;
+        if stats.step_cost(&destination_terrain, None).is_none() {
+            return Err(PathFindingError::Blocked { hex: end });
+        }
+        if start != end && !occupancy.has_room(end, stacking_value) {
+            return Err(PathFindingError::Full { hex: end });
+        }
+        let step_cost = |current: Hex, next: Hex| {
+            let edge = self.edge_between(current, next);
+            let cost = self
+                .terrain_at(next, terrain)
+                .and_then(|terrain| stats.step_cost(&terrain, edge))?;
+            if occupancy.has_room(next, stacking_value) {
+                Some(cost)
+            } else {
+                Some(cost * FULL_HEX_COST_FACTOR)
+            }
+        };
+        bounded_a_star(start, end, MAX_PATH_COST, stats.min_step_cost(), step_cost)
+    }
+
+    /// Game time a unit needs to follow `path` from its first hex to the last, or `None` if
+    /// the unit cannot move along it. Speeds are capped at `speed_limit` like in movement.
+    pub fn travel_time(
+        &self,
+        path: &[Hex],
+        stats: &MovementStats,
+        mode: MovementMode,
+        unit_penalty: f32,
+        speed_limit: Option<Kph>,
+        terrain: &Query<&DifficultTerrain>,
+    ) -> Option<Duration> {
+        path.windows(2).try_fold(Duration::ZERO, |total, pair| {
+            let terrain = self.terrain_at(pair[1], terrain);
+            let edge = self.edge_between(pair[0], pair[1]);
+            let speed = stats.effective_speed(mode, terrain.as_ref(), edge, unit_penalty);
+            let speed = speed_limit.map_or(speed, |limit| speed.min(limit));
+            Some(total + hex_crossing_time(speed)?)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum PathFindingError {
+    /// The destination is not part of the map.
+    OutOfBounds { hex: Hex },
+    /// The destination cannot be entered by the unit.
+    Blocked { hex: Hex },
+    /// The destination has no room left for the unit under the stacking limit.
+    Full { hex: Hex },
+    /// Every route to the destination is more expensive than the budget.
+    NoRouteWithinBudget { budget: u32 },
+    /// No route leads to the destination at all.
+    Unreachable { hex: Hex },
+    /// The unit has no movement stats, as its type is unknown.
+    Immobile,
+    /// The unit is not on the map, e.g. because it travels in a convoy.
+    NotOnMap,
+}
+
+impl std::fmt::Display for PathFindingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathFindingError::OutOfBounds { hex } => {
+                write!(f, "hex ({}, {}) is outside of the map", hex.x, hex.y)
+            }
+            PathFindingError::Blocked { hex } => {
+                write!(f, "hex ({}, {}) cannot be entered", hex.x, hex.y)
+            }
+            PathFindingError::Full { hex } => {
+                write!(f, "hex ({}, {}) is at its stacking limit", hex.x, hex.y)
+            }
+            PathFindingError::NoRouteWithinBudget { budget } => {
+                write!(f, "no route with cost below {budget}")
+            }
+            PathFindingError::Unreachable { hex } => {
+                write!(f, "no route leads to hex ({}, {})", hex.x, hex.y)
+            }
+            PathFindingError::Immobile => write!(f, "unit has no movement stats"),
+            PathFindingError::NotOnMap => write!(f, "unit is not on the map"),
+        }
+    }
+}
+
+impl std::error::Error for PathFindingError {}
+
+/// A* search which stops expanding routes once they cost more than `budget`. No step costs
+/// less than `min_step_cost`, so the distance to `end` times that never overestimates the
+/// remaining cost.
+fn bounded_a_star(
+    start: Hex,
+    end: Hex,
+    budget: u32,
+    min_step_cost: u32,
+    step_cost: impl Fn(Hex, Hex) -> Option<u32>,
+) -> Result<Vec<Hex>, PathFindingError> {
+    let estimate = |hex: Hex| hex.unsigned_distance_to(end) * min_step_cost;
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((estimate(start), [start.x, start.y])));
+    let mut costs = HashMap::default();
+    costs.insert(start, 0);
+    let mut came_from = HashMap::default();
+    let mut over_budget = false;
+    while let Some(Reverse((_, [x, y]))) = open.pop() {
+        let current = Hex::new(x, y);
+        if current == end {
+            let mut path: Vec<Hex> =
+                std::iter::successors(Some(end), |hex| came_from.get(hex).copied()).collect();
+            path.reverse();
+            return Ok(path);
+        }
+        let current_cost = costs[&current];
+        for next in current.all_neighbors() {
+            let Some(cost) = step_cost(current, next) else {
+                continue;
+            };
+            let next_cost = current_cost + cost;
+            let score = next_cost + estimate(next);
+            if score > budget {
+                over_budget = true;
+                continue;
+            }
+            if costs.get(&next).is_some_and(|&known| known <= next_cost) {
+                continue;
+            }
+            costs.insert(next, next_cost);
+            came_from.insert(next, current);
+            open.push(Reverse((score, [next.x, next.y])));
+        }
+    }
+    // Routes cut off by the budget may still have ended somewhere else entirely.
+    Err(if over_budget && reachable(start, end, step_cost) {
+        PathFindingError::NoRouteWithinBudget { budget }
+    } else {
+        PathFindingError::Unreachable { hex: end }
+    })
+}
+
+/// Whether any route leads from `start` to `end`, whatever it costs.
+fn reachable(start: Hex, end: Hex, step_cost: impl Fn(Hex, Hex) -> Option<u32>) -> bool {
+    let mut seen = HashSet::from_iter([start]);
+    let mut stack = vec![start];
+    while let Some(current) = stack.pop() {
+        if current == end {
+            return true;
+        }
+        for next in current.all_neighbors() {
+            if step_cost(current, next).is_some() && seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    false
+}
+
+/// Sprites of the hex atlas depicting each terrain.
+fn atlas_indices(terrain: DifficultTerrain) -> &'static [usize] {
+    match terrain {
+        DifficultTerrain::Open => &[1, 2, 5, 8, 9, 11, 12, 15, 16, 22, 23, 25, 26, 31, 36, 37],
+        DifficultTerrain::Road => &[0, 4, 14, 21, 33, 35],
+        DifficultTerrain::Forest => &[7, 28, 32],
+        DifficultTerrain::Mountain => &[3, 10, 17, 24, 39, 40],
+        DifficultTerrain::River => &[18, 29, 38],
+        DifficultTerrain::Mud => &[6, 13, 19, 20, 27, 30, 34, 41],
+    }
+}
+
+/// Atlas sprite for a hex, varying between the sprites of its terrain so neighbouring hexes
+/// do not all look the same. The same hex always gets the same sprite.
+fn atlas_index(hex: Hex, terrain: DifficultTerrain) -> usize {
+    let indices = atlas_indices(terrain);
+    let variant =
+        (hex.x.wrapping_mul(31) ^ hex.y.wrapping_mul(17)).rem_euclid(indices.len() as i32);
+    indices[variant as usize]
+}
+
+/// Texture of the hex sprites.
+#[derive(Resource)]
+struct HexAtlas {
+    texture: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+}
+
+#[derive(Resource)]
+struct MapHandle(Handle<MapDefinition>);
+
+/// Where the map played on comes from.
+#[derive(Resource, Debug, Clone)]
+pub enum MapSource {
+    /// A `.map.ron` file, relative to the assets folder.
+    File(String),
+    Generated(MapGeneratorSettings),
+}
+
+impl Default for MapSource {
+    fn default() -> Self {
+        MapSource::File(MAP_PATH.to_string())
+    }
+}
+
+/// Sets up an empty grid, which is filled in once the map has loaded.
+fn setup_grid(
+    mut commands: Commands,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut maps: ResMut<Assets<MapDefinition>>,
+    asset_server: Res<AssetServer>,
+    source: Res<MapSource>,
+) {
+    let atlas_layout =
+        TextureAtlasLayout::from_grid(uvec2(120, 140), 7, 6, Some(uvec2(2, 2)), None);
+    commands.insert_resource(HexAtlas {
+        texture: asset_server.load("kenney/hexagonTerrain_sheet.png"),
+        layout: atlas_layouts.add(atlas_layout),
+    });
+    commands.insert_resource(HexGrid {
+        entities: HashMap::default(),
+        edges: HashMap::default(),
+        layout: HexLayout::new(HexOrientation::Pointy).with_rect_size(SPRITE_SIZE),
+    });
+    let handle = match &*source {
+        MapSource::File(path) => asset_server.load(path.clone()),
+        MapSource::Generated(settings) => {
+            let map = generate_map(settings);
+            match validate_map(&map) {
+                Ok(()) => maps.add(map),
+                Err(reason) => {
+                    error!("Generated map is invalid, loading {MAP_PATH} instead: {reason}");
+                    asset_server.load(MAP_PATH)
+                }
+            }
+        }
+    };
+    commands.insert_resource(MapHandle(handle));
+}
+
+/// Spawns the hexes and starting units of the map once the map and the resource and unit
+/// catalogs, which depots and starting units refer to, have loaded. Maps referring to unknown
+/// types are rejected. Maps are only built once, changes to the file need a restart.
+fn build_map(
+    mut commands: Commands,
+    handle: Res<MapHandle>,
+    maps: Res<Assets<MapDefinition>>,
+    atlas: Res<HexAtlas>,
+    resource_types: Res<ResourceTypes>,
+    unit_types: Res<UnitTypeList>,
+    mut grid: ResMut<HexGrid>,
+    mut built: Local<bool>,
+) {
+    if *built || resource_types.types.is_empty() || unit_types.types.is_empty() {
+        return;
+    }
+    let Some(map) = maps.get(&handle.0) else {
+        return;
+    };
+    *built = true;
+    if let Err(reason) = validate_scenario(map, &unit_types, &resource_types) {
+        error!("Map {:?} cannot be played: {reason}", map.name);
+        return;
+    }
+    let parent = commands
+        .spawn((
+            Name::new(format!("Hex Grid ({})", map.name)),
+            Transform::default(),
+        ))
+        .id();
+    for (&coord, &terrain) in map.terrain.iter() {
+        let pos = grid.layout.hex_to_world_pos(coord);
+        let entity = commands
+            .spawn((
+                Sprite {
+                    custom_size: Some(SPRITE_SIZE),
+                    image: atlas.texture.clone(),
+                    texture_atlas: Some(TextureAtlas {
+                        index: atlas_index(coord, terrain),
+                        layout: atlas.layout.clone(),
+                    }),
+                    ..default()
+                },
+                Transform::from_xyz(pos.x, pos.y, 0.0),
+                terrain,
+                HexTile { hex: coord },
+                Name::new(format!("Hex ({}, {})", coord.x, coord.y)),
+            ))
+            .id();
+        commands.entity(parent).add_child(entity);
+        grid.entities.insert(coord, entity);
+    }
+    grid.edges = map.edge_features().into_iter().collect();
+    spawn_edge_overlays(&mut commands, &grid, parent);
+    spawn_scenario(&mut commands, map, &grid, &resource_types);
+    info!(
+        "Built map {:?} with {} hexes",
+        map.name,
+        grid.entities.len()
+    );
+}
+
+fn sync_tranforms(
+    grid: Res<HexGrid>,
+    mut query: Query<(&GamePosition, &mut Transform, &MovingTowards)>,
+) {
+    for (game_pos, mut transform, moving) in query.iter_mut() {
+        let world_pos = grid.to_global_coordinates(game_pos.hex);
+
+        let target_pos = grid.to_global_coordinates(moving.destination);
+        let average = target_pos * (moving.progress / PROGRESS_COMPLETE)
+            + world_pos * (1.0 - moving.progress / PROGRESS_COMPLETE);
+        transform.translation = average.extend(transform.translation.z);
+    }
+}
+
+fn sync_tranforms_stationary(
+    grid: Res<HexGrid>,
+    mut query: Query<
+        (&GamePosition, &mut Transform),
+        (Changed<GamePosition>, Without<MovingTowards>),
+    >,
+) {
+    for (game_pos, mut transform) in query.iter_mut() {
+        let world_pos = grid.to_global_coordinates(game_pos.hex);
+        transform.translation.x = world_pos.x;
+        transform.translation.y = world_pos.y;
+    }
+}
+
+pub struct HexGridPlugin;
+
+impl Plugin for HexGridPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<HexTile>()
+            .register_type::<Depot>()
+            .register_type::<MapLocation>()
+            .register_type::<EdgeOverlay>()
+            .init_asset::<MapDefinition>()
+            .init_asset_loader::<MapLoader>()
+            .init_resource::<MapSource>()
+            .add_systems(Startup, setup_grid)
+            .add_systems(
+                Update,
+                (build_map, sync_tranforms, sync_tranforms_stationary).chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> MovementStats {
+        MovementStats {
+            tactical_speed: 10.0,
+            strategic_speed: 30.0,
+            difficult_terrain_penalty: [
+                (DifficultTerrain::Forest, 2.0),
+                (DifficultTerrain::Road, 0.5),
+            ]
+            .into(),
+            impassable_terrain: [DifficultTerrain::Mountain].into(),
+        }
+    }
+
+    /// Grid of the hexes within `radius` of the origin, with a road along `road`.
+    fn grid(
+        world: &mut World,
+        radius: u32,
+        terrain: impl Fn(Hex) -> DifficultTerrain,
+        road: &[Hex],
+    ) -> HexGrid {
+        let entities = shapes::hexagon(Hex::ZERO, radius)
+            .map(|hex| (hex, world.spawn(terrain(hex)).id()))
+            .collect();
+        let edges = road
+            .windows(2)
+            .filter_map(|pair| HexEdge::new(pair[0], pair[1]))
+            .map(|edge| {
+                let features = EdgeFeatures {
+                    road: Some(RoadGrade::Road),
+                    river: None,
+                };
+                (edge, features)
+            })
+            .collect();
+        HexGrid {
+            entities,
+            edges,
+            layout: HexLayout::new(HexOrientation::Pointy),
+        }
+    }
+
+    fn find_path(
+        world: &mut World,
+        grid: &HexGrid,
+        start: Hex,
+        end: Hex,
+    ) -> Result<Vec<Hex>, PathFindingError> {
+        let mut terrain = world.query::<&DifficultTerrain>();
+        let occupancy = HexOccupancy::default();
+        grid.find_path(start, end, &stats(), &terrain.query(world), &occupancy, 0.0)
+    }
+
+    /// Flat cost of 100 per step inside `radius` of the origin, except into `walls`.
+    fn flat_cost(radius: u32, walls: &[Hex]) -> impl Fn(Hex, Hex) -> Option<u32> {
+        move |_, next| (next.ulength() <= radius && !walls.contains(&next)).then_some(100)
+    }
+
+    #[test]
+    fn destination_outside_of_the_map_is_out_of_bounds() {
+        let mut world = World::new();
+        let grid = grid(&mut world, 3, |_| DifficultTerrain::Open, &[]);
+        let end = Hex::new(10, 0);
+
+        let result = find_path(&mut world, &grid, Hex::ZERO, end);
+
+        assert_eq!(result, Err(PathFindingError::OutOfBounds { hex: end }));
+    }
+
+    #[test]
+    fn walled_off_destination_is_unreachable() {
+        let mut world = World::new();
+        let grid = grid(
+            &mut world,
+            4,
+            |hex| match hex.ulength() {
+                1 => DifficultTerrain::Mountain,
+                _ => DifficultTerrain::Open,
+            },
+            &[],
+        );
+        let start = Hex::new(3, 0);
+
+        let result = find_path(&mut world, &grid, start, Hex::ZERO);
+
+        assert_eq!(
+            result,
+            Err(PathFindingError::Unreachable { hex: Hex::ZERO })
+        );
+    }
+
+    #[test]
+    fn routes_cut_off_by_the_budget_do_not_hide_an_unreachable_destination() {
+        let walls: Vec<Hex> = Hex::ZERO.all_neighbors().to_vec();
+
+        let result = bounded_a_star(Hex::new(3, 0), Hex::ZERO, 150, 100, flat_cost(4, &walls));
+
+        assert_eq!(
+            result,
+            Err(PathFindingError::Unreachable { hex: Hex::ZERO })
+        );
+    }
+
+    #[test]
+    fn route_more_expensive_than_the_budget_is_reported() {
+        let end = Hex::new(3, 0);
+
+        let cut_off = bounded_a_star(Hex::ZERO, end, 250, 100, flat_cost(4, &[]));
+        let within = bounded_a_star(Hex::ZERO, end, 300, 100, flat_cost(4, &[]));
+
+        assert_eq!(
+            cut_off,
+            Err(PathFindingError::NoRouteWithinBudget { budget: 250 })
+        );
+        assert_eq!(within.map(|path| path.len()), Ok(4));
+    }
+
+    #[test]
+    fn longer_route_along_a_road_is_preferred() {
+        let mut world = World::new();
+        let road = [
+            Hex::ZERO,
+            Hex::new(0, 1),
+            Hex::new(1, 1),
+            Hex::new(2, 1),
+            Hex::new(3, 0),
+        ];
+        let grid = grid(&mut world, 4, |_| DifficultTerrain::Forest, &road);
+
+        let result = find_path(&mut world, &grid, Hex::ZERO, Hex::new(3, 0));
+
+        assert_eq!(result, Ok(road.to_vec()));
+    }
+}
@@ -0,0 +1,118 @@
+use bevy::{
+    color::Color,
+    ecs::{component::Component, entity::Entity, name::Name, system::Commands},
+    math::Vec2,
+    platform::collections::HashMap,
+    reflect::Reflect,
+    sprite::{Sprite, Text2d},
+    text::TextFont,
+    transform::components::Transform,
+    utils::default,
+};
+use hexx::Hex;
+
+use crate::{
+    map::{HexGrid, MapDefinition},
+    movement::GamePosition,
+    resources::ResourceTypes,
+    units::{AtomicUnitBundle, AttachedTo, CompositeUnitBundle, Faction, SupplyStorage},
+};
+
+const DEPOT_COLOR: Color = Color::srgb(0.55, 0.4, 0.2);
+const DEPOT_SIZE: Vec2 = Vec2::new(14.0, 14.0);
+/// Above the hex tiles, below units.
+const DEPOT_Z: f32 = 0.5;
+const LOCATION_LABEL_Z: f32 = 4.0;
+const LOCATION_LABEL_OFFSET: Vec2 = Vec2::new(0.0, -12.0);
+
+/// A supply dump placed by the map. Depots have no capacity limits.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+pub struct Depot;
+
+/// A named place on the map.
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct MapLocation {
+    pub name: String,
+    pub hex: Hex,
+}
+
+/// Spawns the location labels, depots and starting units of a map, which has to have passed
+/// `validate_scenario`.
+pub fn spawn_scenario(
+    commands: &mut Commands,
+    map: &MapDefinition,
+    grid: &HexGrid,
+    resource_types: &ResourceTypes,
+) {
+    for location in map.locations.iter() {
+        let position = grid.to_global_coordinates(location.hex) + LOCATION_LABEL_OFFSET;
+        commands.spawn((
+            Name::new(location.name.clone()),
+            MapLocation {
+                name: location.name.clone(),
+                hex: location.hex,
+            },
+            Text2d::new(location.name.clone()),
+            TextFont {
+                font_size: 9.0,
+                ..default()
+            },
+            Transform::from_translation(position.extend(LOCATION_LABEL_Z)),
+        ));
+    }
+
+    for depot in map.depots.iter() {
+        let mut storage = SupplyStorage::new(None, None);
+        for stack in depot
+            .supplies
+            .iter()
+            .filter_map(|supply| supply.resolve(resource_types).ok())
+        {
+            storage.insert(stack);
+        }
+        commands.spawn((
+            Name::new(depot.name.clone()),
+            Depot,
+            Faction(depot.faction.clone()),
+            GamePosition { hex: depot.hex },
+            storage,
+            Sprite {
+                custom_size: Some(DEPOT_SIZE),
+                color: DEPOT_COLOR,
+                ..default()
+            },
+            Transform::from_xyz(0.0, 0.0, DEPOT_Z),
+        ));
+    }
+
+    let formations: HashMap<&str, Entity> = map
+        .formations
+        .iter()
+        .map(|formation| {
+            let entity = commands
+                .spawn((
+                    CompositeUnitBundle::new(formation.name.clone(), formation.echelon),
+                    Faction(formation.faction.clone()),
+                ))
+                .id();
+            (formation.name.as_str(), entity)
+        })
+        .collect();
+    for formation in map.formations.iter() {
+        if let Some(superior) = &formation.attached_to {
+            commands
+                .entity(formations[formation.name.as_str()])
+                .insert(AttachedTo(formations[superior.as_str()]));
+        }
+    }
+
+    for unit in map.units.iter() {
+        let mut entity = commands.spawn((
+            AtomicUnitBundle::new(unit.unit_type.clone(), unit.hex),
+            Faction(unit.faction.clone()),
+        ));
+        if let Some(formation) = &unit.attached_to {
+            entity.insert(AttachedTo(formations[formation.as_str()]));
+        }
+    }
+}
@@ -1,12 +1,24 @@
 mod path_finding;
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use bevy::{
-    ecs::{component::Component, entity::Entity, event::Event, message::Message},
+    ecs::{
+        component::Component, entity::Entity, event::Event, message::Message,
+        reflect::ReflectComponent,
+    },
     reflect::Reflect,
 };
+use serde::Deserialize;
 
-use crate::{map::HexPosition, movement::path_finding::PathFindingPlugin};
+use crate::{
+    map::{
+        EdgeFeatures, HEX_RADIUS_IN_METERS, HexPosition, PathFindingError, RiverCrossing, RoadGrade,
+    },
+    movement::path_finding::PathFindingPlugin,
+};
 
 pub type Kph = f32;
 
@@ -20,10 +32,15 @@ impl bevy::app::Plugin for MovementPlugin {
             .register_type::<MovementStats>()
             .register_type::<MovementPenaltyReason>()
             .register_type::<MovemenetPenalty>()
+            .register_type::<MovementPenalties>()
             .register_type::<Path>()
             .register_type::<GamePosition>()
             .register_type::<MovingTowards>()
+            .register_type::<DistanceTravelled>()
+            .register_type::<SpeedLimit>()
             .add_event::<MoveUnitMessage>()
+            .add_message::<PathNotFoundMessage>()
+            .add_message::<DestinationReachedMessage>()
             .add_plugins(PathFindingPlugin);
     }
 }
@@ -39,33 +56,163 @@ pub struct MovementConfig {
     pub mode: MovementMode,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
-pub enum DifficultTerrain {}
+/// Terrain of a single hex. Attached to every hex entity spawned by the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Default, Component, Deserialize)]
+pub enum DifficultTerrain {
+    #[default]
+    Open,
+    Road,
+    Forest,
+    Mountain,
+    River,
+    Mud,
+}
+
+/// Cost of entering a hex with no terrain penalty, in path finding units.
+pub const BASE_STEP_COST: f32 = 100.0;
 
-#[derive(Debug, Clone, Reflect, Default)]
+/// Movement stats of a unit type. When attached to an entity, as with convoys, they take
+/// precedence over the stats of its unit type.
+#[derive(Debug, Clone, Reflect, Default, Deserialize, Component)]
+#[reflect(Component)]
 pub struct MovementStats {
     pub tactical_speed: Kph,
     pub strategic_speed: Kph,
+    /// Multiplier applied to the time needed to cross a hex of given terrain.
+    #[serde(default)]
     pub difficult_terrain_penalty: HashMap<DifficultTerrain, f32>,
+    #[serde(default)]
+    pub impassable_terrain: HashSet<DifficultTerrain>,
 }
 
 impl MovementStats {
+    pub fn speed(&self, mode: MovementMode) -> Kph {
+        match mode {
+            MovementMode::Tactical => self.tactical_speed,
+            MovementMode::Strategic => self.strategic_speed,
+        }
+    }
+
     pub fn get_penalty(&self, terrain: &DifficultTerrain) -> f32 {
         *self.difficult_terrain_penalty.get(terrain).unwrap_or(&1.0)
     }
+
+    /// Multiplier on the time needed to enter a hex of given terrain across an edge, or `None`
+    /// if the unit cannot make the step. Following a road, the unit moves at its road pace
+    /// whatever the terrain, though roads do not open up terrain the unit cannot enter;
+    /// fording a river slows it down as river terrain would.
+    pub fn step_penalty(
+        &self,
+        terrain: &DifficultTerrain,
+        edge: Option<&EdgeFeatures>,
+    ) -> Option<f32> {
+        if self.impassable_terrain.contains(terrain) {
+            return None;
+        }
+        let edge = edge.copied().unwrap_or_default();
+        let mut penalty = match edge.road {
+            Some(grade) => self.get_penalty(&DifficultTerrain::Road) * grade.penalty(),
+            None => self.get_penalty(terrain),
+        };
+        match edge.river {
+            Some(RiverCrossing::Impassable) => return None,
+            Some(RiverCrossing::Ford) => {
+                if self.impassable_terrain.contains(&DifficultTerrain::River) {
+                    return None;
+                }
+                penalty *= self.get_penalty(&DifficultTerrain::River);
+            }
+            Some(RiverCrossing::Bridge) | None => {}
+        }
+        Some(penalty)
+    }
+
+    /// Speed into a hex of given terrain across an edge once the step and `unit_penalty`
+    /// multipliers are applied, zero if the unit cannot make the step.
+    pub fn effective_speed(
+        &self,
+        mode: MovementMode,
+        terrain: Option<&DifficultTerrain>,
+        edge: Option<&EdgeFeatures>,
+        unit_penalty: f32,
+    ) -> Kph {
+        let step_penalty = match terrain {
+            Some(terrain) => self.step_penalty(terrain, edge),
+            None => Some(1.0),
+        };
+        step_penalty.map_or(0.0, |step_penalty| {
+            self.speed(mode) / (step_penalty * unit_penalty)
+        })
+    }
+
+    /// Cost of entering a hex of given terrain across an edge, or `None` if the unit cannot
+    /// make the step at all.
+    pub fn step_cost(
+        &self,
+        terrain: &DifficultTerrain,
+        edge: Option<&EdgeFeatures>,
+    ) -> Option<u32> {
+        let penalty = self.step_penalty(terrain, edge)?;
+        Some((BASE_STEP_COST * penalty).round().max(1.0) as u32)
+    }
+
+    /// Lower bound of [`Self::step_cost`] over every terrain and edge, so path finding can
+    /// estimate the remaining cost of a route without overestimating it.
+    pub fn min_step_cost(&self) -> u32 {
+        let terrain = self
+            .difficult_terrain_penalty
+            .iter()
+            .filter(|(terrain, _)| !self.impassable_terrain.contains(terrain))
+            .fold(1.0, |min: f32, (_, &penalty)| min.min(penalty));
+        let road = [RoadGrade::Track, RoadGrade::Road, RoadGrade::Highway]
+            .iter()
+            .map(|grade| self.get_penalty(&DifficultTerrain::Road) * grade.penalty())
+            .fold(f32::INFINITY, f32::min);
+        let ford = if self.impassable_terrain.contains(&DifficultTerrain::River) {
+            1.0
+        } else {
+            self.get_penalty(&DifficultTerrain::River).min(1.0)
+        };
+        (BASE_STEP_COST * terrain.min(road) * ford).round().max(1.0) as u32
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
 pub enum MovementPenaltyReason {
     Terrain(DifficultTerrain),
+    SupplyShortage,
+    Darkness,
+    Overstacked,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Reflect, Component)]
+/// Multiplier applied to the time a unit needs to cross a hex.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
 pub struct MovemenetPenalty {
     pub value: f32,
     pub reason: MovementPenaltyReason,
 }
 
+/// Penalties currently slowing a unit down, at most one per reason.
+#[derive(Debug, Clone, Default, Reflect, Component)]
+pub struct MovementPenalties {
+    pub penalties: Vec<MovemenetPenalty>,
+}
+
+impl MovementPenalties {
+    pub fn set(&mut self, penalty: MovemenetPenalty) {
+        self.remove(penalty.reason);
+        self.penalties.push(penalty);
+    }
+
+    pub fn remove(&mut self, reason: MovementPenaltyReason) {
+        self.penalties.retain(|p| p.reason != reason);
+    }
+
+    pub fn total(&self) -> f32 {
+        self.penalties.iter().map(|p| p.value).product()
+    }
+}
+
 #[derive(Component, Debug, Reflect)]
 pub struct Path {
     pub waypoints: Vec<HexPosition>,
@@ -83,6 +230,42 @@ pub struct MoveUnitMessage {
     pub destination: HexPosition,
 }
 
+/// Sent when a unit was ordered to move to a destination it cannot reach.
+#[derive(Message, Debug)]
+pub struct PathNotFoundMessage {
+    pub unit: Entity,
+    pub destination: HexPosition,
+    pub error: PathFindingError,
+}
+
+/// Caps the speed of a unit regardless of its type and terrain.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+pub struct SpeedLimit {
+    pub kph: Kph,
+}
+
+/// Sent when a unit arrives at the last hex of its path.
+#[derive(Message, Debug)]
+pub struct DestinationReachedMessage {
+    pub unit: Entity,
+    pub destination: HexPosition,
+}
+
+/// Distance covered since the counter was last reset by a system consuming it.
+#[derive(Component, Debug, Default, Reflect)]
+pub struct DistanceTravelled {
+    pub kilometers: f32,
+}
+
+/// Game time needed to cross a single hex at `speed`, `None` for units which cannot move or
+/// are so slow the time does not fit into a `Duration`.
+pub fn hex_crossing_time(speed: Kph) -> Option<Duration> {
+    if speed <= 0.0 {
+        return None;
+    }
+    Duration::try_from_secs_f32(HEX_RADIUS_IN_METERS / 1000.0 / speed * 3600.0).ok()
+}
+
 pub const PROGRESS_ZERO: f32 = 0.0;
 pub const PROGRESS_COMPLETE: f32 = 100.0;
 #[derive(Component, Debug, Reflect)]
@@ -99,3 +282,83 @@ impl MovingTowards {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Slow in forests, fast on roads, able to ford rivers but not to climb mountains.
+    fn stats() -> MovementStats {
+        MovementStats {
+            tactical_speed: 10.0,
+            strategic_speed: 30.0,
+            difficult_terrain_penalty: [
+                (DifficultTerrain::Forest, 2.0),
+                (DifficultTerrain::Road, 0.5),
+                (DifficultTerrain::River, 1.5),
+            ]
+            .into(),
+            impassable_terrain: [DifficultTerrain::Mountain].into(),
+        }
+    }
+
+    fn edge(road: Option<RoadGrade>, river: Option<RiverCrossing>) -> EdgeFeatures {
+        EdgeFeatures { road, river }
+    }
+
+    #[test]
+    fn road_sets_the_pace_through_difficult_terrain() {
+        let highway = edge(Some(RoadGrade::Highway), None);
+
+        let penalty = stats().step_penalty(&DifficultTerrain::Forest, Some(&highway));
+
+        assert_eq!(penalty, Some(0.4));
+    }
+
+    #[test]
+    fn road_does_not_open_impassable_terrain() {
+        let road = edge(Some(RoadGrade::Road), None);
+
+        let penalty = stats().step_penalty(&DifficultTerrain::Mountain, Some(&road));
+
+        assert_eq!(penalty, None);
+    }
+
+    #[test]
+    fn fording_slows_down_like_river_terrain() {
+        let ford = edge(None, Some(RiverCrossing::Ford));
+        let mut wheeled = stats();
+        wheeled.impassable_terrain.insert(DifficultTerrain::River);
+
+        let penalty = stats().step_penalty(&DifficultTerrain::Forest, Some(&ford));
+        let wheeled_penalty = wheeled.step_penalty(&DifficultTerrain::Open, Some(&ford));
+
+        assert_eq!(penalty, Some(3.0));
+        assert_eq!(wheeled_penalty, None);
+    }
+
+    #[test]
+    fn bridges_cross_rivers_without_delay() {
+        let bridge = edge(Some(RoadGrade::Road), Some(RiverCrossing::Bridge));
+        let mut wheeled = stats();
+        wheeled.impassable_terrain.insert(DifficultTerrain::River);
+
+        let penalty = wheeled.step_penalty(&DifficultTerrain::Open, Some(&bridge));
+
+        assert_eq!(penalty, Some(0.5));
+    }
+
+    #[test]
+    fn rivers_without_crossing_cannot_be_crossed_even_by_road() {
+        let river = edge(Some(RoadGrade::Road), Some(RiverCrossing::Impassable));
+
+        let penalty = stats().step_penalty(&DifficultTerrain::Open, Some(&river));
+
+        assert_eq!(penalty, None);
+    }
+
+    #[test]
+    fn cheapest_step_is_along_a_highway() {
+        assert_eq!(stats().min_step_cost(), 40);
+    }
+}
@@ -1,66 +1,174 @@
 use bevy::{
-    app::{Plugin, Update},
+    app::{FixedUpdate, Plugin, Update},
     ecs::{
         entity::Entity,
         event::EventReader,
+        message::MessageWriter,
         system::{Commands, Query, Res},
     },
-    log::debug,
+    log::{debug, warn},
     time::{Fixed, Time},
 };
 
 use crate::{
-    map::{HEX_RADIUS_IN_METERS, HexGrid},
+    map::{HEX_RADIUS_IN_METERS, HexGrid, PathFindingError},
     movement::{
-        GamePosition, Kph, MoveUnitMessage, MovementConfig, MovingTowards, PROGRESS_COMPLETE,
-        PROGRESS_ZERO, Path,
+        DestinationReachedMessage, DifficultTerrain, DistanceTravelled, GamePosition,
+        MoveUnitMessage, MovementConfig, MovementPenalties, MovementStats, MovingTowards,
+        PROGRESS_COMPLETE, PROGRESS_ZERO, Path, PathNotFoundMessage, SpeedLimit,
     },
+    time::game_delta,
+    units::{HexOccupancy, Unit, UnitTypeList},
 };
 
 pub struct PathFindingPlugin;
 
 impl Plugin for PathFindingPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Update, (calculate_path, move_unit_along_path));
+        app.add_systems(Update, calculate_path)
+            .add_systems(FixedUpdate, move_unit_along_path);
     }
 }
 
 fn calculate_path(
     mut events: EventReader<MoveUnitMessage>,
-    query: Query<(&GamePosition, &MovementConfig)>,
+    query: Query<(
+        &GamePosition,
+        &Unit,
+        Option<&MovementStats>,
+        Option<&MovingTowards>,
+    )>,
+    terrain: Query<&DifficultTerrain>,
+    unit_types: Res<UnitTypeList>,
+    occupancy: Res<HexOccupancy>,
     grid: Res<HexGrid>,
+    mut not_found: MessageWriter<PathNotFoundMessage>,
+    mut reached: MessageWriter<DestinationReachedMessage>,
     mut commands: Commands,
 ) {
     for event in events.read() {
-        if let Ok((current_pos, config)) = query.get(event.unit) {
-            debug!(
-                target: "pathfinding",
-                "Calculating path for unit {:?} from {:?} to {:?}",
-                event.unit,
-                current_pos.hex, event.destination
-            );
-            let path = grid.find_path(current_pos.hex, event.destination, config);
-            if let Some(first) = path.first().copied() {
+        let Ok((current_pos, unit, own_stats, moving)) = query.get(event.unit) else {
+            not_found.write(PathNotFoundMessage {
+                unit: event.unit,
+                destination: event.destination,
+                error: PathFindingError::NotOnMap,
+            });
+            continue;
+        };
+        debug!(
+            target: "pathfinding",
+            "Calculating path for unit {:?} from {:?} to {:?}",
+            event.unit,
+            current_pos.hex, event.destination
+        );
+        let path = match own_stats
+            .or_else(|| unit_types.movement_stats(unit))
+            .ok_or(PathFindingError::Immobile)
+            .and_then(|stats| {
+                grid.find_path(
+                    current_pos.hex,
+                    event.destination,
+                    stats,
+                    &terrain,
+                    &occupancy,
+                    occupancy.stacking_value_of(event.unit),
+                )
+            }) {
+            Ok(path) => path,
+            Err(error) => {
+                debug!(target: "pathfinding", "No path for unit {:?} to {:?}: {}", event.unit, event.destination, error);
+                not_found.write(PathNotFoundMessage {
+                    unit: event.unit,
+                    destination: event.destination,
+                    error,
+                });
+                continue;
+            }
+        };
+        match (path.get(1).copied(), moving) {
+            // A unit halfway into the next hex of its new path carries on.
+            (Some(next), Some(moving)) if moving.destination == next => {
+                commands.entity(event.unit).insert(Path { waypoints: path });
+            }
+            (Some(next), _) => {
                 commands
                     .entity(event.unit)
-                    .insert((Path { waypoints: path }, MovingTowards::new(first)));
+                    .insert((Path { waypoints: path }, MovingTowards::new(next)));
+            }
+            // Already there, but on the way out: head back into the hex.
+            (None, Some(_)) => {
+                commands.entity(event.unit).insert((
+                    Path { waypoints: path },
+                    MovingTowards::new(current_pos.hex),
+                ));
+            }
+            (None, None) => {
+                reached.write(DestinationReachedMessage {
+                    unit: event.unit,
+                    destination: current_pos.hex,
+                });
             }
         }
     }
 }
 
-const TEMPORARY_MOVE_SPEED: Kph = 10.0;
-
 fn move_unit_along_path(
-    mut query: Query<(Entity, &mut MovingTowards, &mut Path, &mut GamePosition)>,
+    mut query: Query<(
+        Entity,
+        &Unit,
+        &MovementConfig,
+        Option<&MovementPenalties>,
+        Option<&mut DistanceTravelled>,
+        Option<&SpeedLimit>,
+        Option<&MovementStats>,
+        &mut MovingTowards,
+        &mut Path,
+        &mut GamePosition,
+    )>,
+    terrain: Query<&DifficultTerrain>,
+    unit_types: Res<UnitTypeList>,
+    grid: Res<HexGrid>,
     time: Res<Time<Fixed>>,
+    mut reached: MessageWriter<DestinationReachedMessage>,
+    mut not_found: MessageWriter<PathNotFoundMessage>,
     mut commands: Commands,
 ) {
-    for (entity, mut moving, mut path, mut position) in query.iter_mut() {
-        moving.progress +=
-            (TEMPORARY_MOVE_SPEED / HEX_RADIUS_IN_METERS * 1000.0 * time.delta_secs() * 60.0
-                / 3600.0)
-                * PROGRESS_COMPLETE;
+    for (
+        entity,
+        unit,
+        config,
+        penalties,
+        distance,
+        speed_limit,
+        own_stats,
+        mut moving,
+        mut path,
+        mut position,
+    ) in query.iter_mut()
+    {
+        // Units without stats are never given a path, but their type may have gone away since.
+        let Some(stats) = own_stats.or_else(|| unit_types.movement_stats(unit)) else {
+            warn!(target: "movement", "Unit {entity:?} of unknown type {:?} stopped", unit.unit_type);
+            commands.entity(entity).remove::<(MovingTowards, Path)>();
+            not_found.write(PathNotFoundMessage {
+                unit: entity,
+                destination: path.waypoints.last().copied().unwrap_or(moving.destination),
+                error: PathFindingError::Immobile,
+            });
+            continue;
+        };
+        let speed = stats.effective_speed(
+            config.mode,
+            grid.terrain_at(moving.destination, &terrain).as_ref(),
+            grid.edge_between(position.hex, moving.destination),
+            penalties.map_or(1.0, MovementPenalties::total),
+        );
+        let speed = speed_limit.map_or(speed, |limit| speed.min(limit.kph));
+        let kilometers = speed * game_delta(&time).as_secs_f32() / 3600.0;
+        if let Some(mut distance) = distance {
+            distance.kilometers += kilometers;
+        }
+        moving.progress += kilometers * 1000.0 / HEX_RADIUS_IN_METERS * PROGRESS_COMPLETE;
         debug!(target: "movement", "Entity {:?} progressed to {:?}%", entity, moving.progress / PROGRESS_COMPLETE * 100.0);
         if moving.progress >= PROGRESS_COMPLETE {
             debug!(target: "movement", "Entity {:?} reached hex {:?}", entity, moving.destination);
@@ -81,6 +189,10 @@ fn move_unit_along_path(
                 debug!(target: "movement", "Entity {:?} reached final destination hex {:?}", entity, moving.destination);
                 commands.entity(entity).remove::<(MovingTowards, Path)>();
                 path.waypoints.clear();
+                reached.write(DestinationReachedMessage {
+                    unit: entity,
+                    destination: moving.destination,
+                });
             }
         }
     }
@@ -0,0 +1,191 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::{
+        Asset, AssetApp, AssetEvent, AssetLoader, AssetServer, Assets, Handle, LoadContext,
+        io::Reader,
+    },
+    ecs::{
+        message::MessageReader,
+        resource::Resource,
+        system::{Commands, Res, ResMut},
+    },
+    log::{info, warn},
+    platform::collections::HashSet,
+    reflect::TypePath,
+};
+use serde::Deserialize;
+
+use crate::resources::{
+    PalletizationInfo, ResourceKey, ResourceType, ResourceTypeId, ResourceTypes, VolumeInLitters,
+    WeightInKilograms,
+};
+
+pub const RESOURCE_TYPES_PATH: &str = "resources/resource_types.resources.ron";
+
+pub struct ResourceCatalogPlugin;
+
+impl Plugin for ResourceCatalogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<ResourceCatalog>()
+            .init_asset_loader::<ResourceCatalogLoader>()
+            .add_systems(Startup, load_resource_catalog)
+            .add_systems(Update, update_resource_types);
+    }
+}
+
+/// Contents of a `.resources.ron` file.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct ResourceCatalog {
+    pub resources: Vec<ResourceDefinition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResourceDefinition {
+    pub key: ResourceKey,
+    pub name: String,
+    pub unit_weight: WeightInKilograms,
+    pub unit_volume: VolumeInLitters,
+    #[serde(default)]
+    pub palletization: PalletizationInfo,
+}
+
+#[derive(Resource)]
+struct ResourceCatalogHandle(Handle<ResourceCatalog>);
+
+#[derive(Debug)]
+pub enum ResourceCatalogError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Duplicate { key: ResourceKey },
+    Invalid { key: ResourceKey, reason: String },
+}
+
+impl std::fmt::Display for ResourceCatalogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResourceCatalogError::Io(error) => write!(f, "could not read resource types: {error}"),
+            ResourceCatalogError::Parse(error) => {
+                write!(f, "could not parse resource types: {error}")
+            }
+            ResourceCatalogError::Duplicate { key } => {
+                write!(f, "resource type {key:?} is defined more than once")
+            }
+            ResourceCatalogError::Invalid { key, reason } => {
+                write!(f, "resource type {key:?} is invalid: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResourceCatalogError {}
+
+#[derive(Default)]
+struct ResourceCatalogLoader;
+
+impl AssetLoader for ResourceCatalogLoader {
+    type Asset = ResourceCatalog;
+    type Settings = ();
+    type Error = ResourceCatalogError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(ResourceCatalogError::Io)?;
+        let catalog: ResourceCatalog =
+            ron::de::from_bytes(&bytes).map_err(ResourceCatalogError::Parse)?;
+        let mut keys = HashSet::new();
+        for definition in &catalog.resources {
+            if !keys.insert(definition.key.as_str()) {
+                return Err(ResourceCatalogError::Duplicate {
+                    key: definition.key.clone(),
+                });
+            }
+            validate(definition).map_err(|reason| ResourceCatalogError::Invalid {
+                key: definition.key.clone(),
+                reason,
+            })?;
+        }
+        Ok(catalog)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["resources.ron"]
+    }
+}
+
+fn validate(definition: &ResourceDefinition) -> Result<(), String> {
+    if definition.key.is_empty() {
+        return Err("key must not be empty".into());
+    }
+    for (name, value) in [
+        ("unit_weight", definition.unit_weight),
+        ("unit_volume", definition.unit_volume),
+    ] {
+        if !value.is_finite() || value < 0.0 {
+            return Err(format!("{name} must be a non-negative number, got {value}"));
+        }
+    }
+    if let PalletizationInfo::Palletized {
+        units_per_pallet: 0,
+    } = definition.palletization
+    {
+        return Err("units_per_pallet must be greater than zero".into());
+    }
+    Ok(())
+}
+
+fn load_resource_catalog(mut commands: Commands, server: Res<AssetServer>) {
+    commands.insert_resource(ResourceCatalogHandle(server.load(RESOURCE_TYPES_PATH)));
+}
+
+/// Rebuilds [`ResourceTypes`] from the catalog. Resource types which were already known keep
+/// their [`ResourceTypeId`], so stacks created before a reload stay valid.
+fn update_resource_types(
+    mut events: MessageReader<AssetEvent<ResourceCatalog>>,
+    handle: Res<ResourceCatalogHandle>,
+    catalogs: Res<Assets<ResourceCatalog>>,
+    mut resource_types: ResMut<ResourceTypes>,
+) {
+    for event in events.read() {
+        let reloaded = event.is_loaded_with_dependencies(&handle.0) || event.is_modified(&handle.0);
+        if !reloaded {
+            continue;
+        }
+        let Some(catalog) = catalogs.get(&handle.0) else {
+            continue;
+        };
+        for definition in &catalog.resources {
+            let resource_type = ResourceType {
+                key: definition.key.clone(),
+                name: definition.name.clone(),
+                unit_weight: definition.unit_weight,
+                unit_volume: definition.unit_volume,
+                palletization: definition.palletization,
+            };
+            match resource_types.ids.get(&definition.key).copied() {
+                Some(id) => resource_types.types[id as usize] = resource_type,
+                None => {
+                    let id = resource_types.types.len() as ResourceTypeId;
+                    resource_types.ids.insert(definition.key.clone(), id);
+                    resource_types.types.push(resource_type);
+                }
+            }
+        }
+        for resource_type in &resource_types.types {
+            if !catalog.resources.iter().any(|d| d.key == resource_type.key) {
+                warn!(
+                    "Resource type {:?} was removed from the catalog but is kept until restart",
+                    resource_type.key
+                );
+            }
+        }
+        info!("Loaded {} resource types", catalog.resources.len());
+    }
+}
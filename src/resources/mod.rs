@@ -1,4 +1,8 @@
-use bevy::{ecs::resource::Resource, reflect::Reflect};
+mod catalog;
+use bevy::{ecs::resource::Resource, platform::collections::HashMap, reflect::Reflect};
+use serde::Deserialize;
+
+use crate::resources::catalog::ResourceCatalogPlugin;
 
 pub struct ResourcesPlugin;
 impl bevy::app::Plugin for ResourcesPlugin {
@@ -10,7 +14,8 @@ impl bevy::app::Plugin for ResourcesPlugin {
             .init_resource::<ResourceTypes>()
             .insert_resource::<PalletizationConfig>(PalletizationConfig {
                 pallete_volume: 50.0,
-            });
+            })
+            .add_plugins(ResourceCatalogPlugin);
     }
 }
 
@@ -20,18 +25,45 @@ pub struct PalletizationConfig {
 }
 
 pub type ResourceTypeId = u32;
+/// Identifier of a resource type used in asset files. Unlike [`ResourceTypeId`] it does not
+/// depend on the order in which resource types are defined.
+pub type ResourceKey = String;
 pub type VolumeInLitters = f32;
 pub type WeightInKilograms = f32;
 
-#[derive(Debug, Reflect, Default)]
+#[derive(Debug, Reflect, Default, Clone)]
 pub struct ResourceType {
+    pub key: ResourceKey,
     pub name: String,
     pub unit_weight: WeightInKilograms,
     pub unit_volume: VolumeInLitters,
     pub palletization: PalletizationInfo,
 }
 
-#[derive(Debug, Reflect, Default)]
+impl ResourceType {
+    /// Weight and volume of a single unit of `amount`, i.e. of one item for fluid stock or of
+    /// one loaded pallet, including the pallet itself. `None` if the resource cannot be
+    /// palletized but `amount` is in pallets.
+    pub fn footprint(
+        &self,
+        amount: &ResourceAmout,
+        config: &PalletizationConfig,
+    ) -> Option<(WeightInKilograms, VolumeInLitters)> {
+        match (amount, self.palletization) {
+            (ResourceAmout::Fluid { .. }, _) => Some((self.unit_weight, self.unit_volume)),
+            (ResourceAmout::Pallets { .. }, PalletizationInfo::Palletized { units_per_pallet }) => {
+                let units = units_per_pallet as f32;
+                Some((
+                    self.unit_weight * units,
+                    self.unit_volume * units + config.pallete_volume,
+                ))
+            }
+            (ResourceAmout::Pallets { .. }, PalletizationInfo::CannotBePalletized) => None,
+        }
+    }
+}
+
+#[derive(Debug, Reflect, Default, Clone, Copy, PartialEq, Deserialize)]
 pub enum PalletizationInfo {
     #[default]
     CannotBePalletized,
@@ -43,16 +75,87 @@ pub enum PalletizationInfo {
 #[derive(Reflect, Default, Resource)]
 pub struct ResourceTypes {
     pub types: Vec<ResourceType>,
+    pub ids: HashMap<ResourceKey, ResourceTypeId>,
 }
 
-#[derive(Debug, Reflect)]
+impl ResourceTypes {
+    pub fn get(&self, id: ResourceTypeId) -> Option<&ResourceType> {
+        self.types.get(id as usize)
+    }
+
+    pub fn resolve(&self, key: &str) -> Result<ResourceTypeId, UnknownResourceError> {
+        self.ids
+            .get(key)
+            .copied()
+            .ok_or_else(|| UnknownResourceError { key: key.into() })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownResourceError {
+    pub key: ResourceKey,
+}
+
+impl std::fmt::Display for UnknownResourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown resource type {:?}", self.key)
+    }
+}
+
+impl std::error::Error for UnknownResourceError {}
+
+#[derive(Debug, Reflect, Clone, Copy, PartialEq)]
 pub struct ResourceStack {
     pub resource_type: ResourceTypeId,
     pub amount: ResourceAmout,
 }
 
-#[derive(Debug, Reflect)]
+#[derive(Debug, Reflect, Clone, Copy, PartialEq, Deserialize)]
 pub enum ResourceAmout {
     Fluid { count: f32 },
     Pallets { count: u32 },
 }
+
+impl ResourceAmout {
+    pub fn count(&self) -> f32 {
+        match self {
+            ResourceAmout::Fluid { count } => *count,
+            ResourceAmout::Pallets { count } => *count as f32,
+        }
+    }
+
+    /// Amount of the same kind with a different count. Pallet counts are rounded down.
+    pub fn with_count(&self, count: f32) -> Self {
+        match self {
+            ResourceAmout::Fluid { .. } => ResourceAmout::Fluid { count },
+            ResourceAmout::Pallets { .. } => ResourceAmout::Pallets {
+                count: count.max(0.0).floor() as u32,
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() <= 0.0
+    }
+
+    /// Whether both amounts are fluid or both are palletized.
+    pub fn same_kind(&self, other: &ResourceAmout) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// A [`ResourceStack`] as written in asset files, referring to the resource type by its key.
+#[derive(Debug, Reflect, Clone, Deserialize)]
+pub struct ResourceStackDefinition {
+    pub resource: ResourceKey,
+    pub amount: ResourceAmout,
+}
+
+impl ResourceStackDefinition {
+    pub fn resolve(&self, types: &ResourceTypes) -> Result<ResourceStack, UnknownResourceError> {
+        Ok(ResourceStack {
+            resource_type: types.resolve(&self.resource)?,
+            amount: self.amount,
+        })
+    }
+}
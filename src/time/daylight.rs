@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{App, FixedUpdate, Plugin, Update},
+    color::{Color, Mix},
+    ecs::{
+        change_detection::DetectChanges,
+        query::With,
+        reflect::ReflectResource,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Local, Query, Res, ResMut},
+    },
+    reflect::Reflect,
+    sprite::Sprite,
+};
+
+use crate::{
+    map::HexTile,
+    movement::{MovemenetPenalty, MovementPenalties, MovementPenaltyReason},
+    time::{CurrentTimePoint, advance_game_time},
+};
+
+/// Tint applied at full night. Dawn and dusk blend between this and full daylight.
+const NIGHT_TINT: Color = Color::srgb(0.25, 0.3, 0.5);
+/// Brightness changes smaller than this are not applied to the grid, so the tint is updated
+/// a few times per transition instead of every frame.
+const TINT_STEP: f32 = 0.05;
+
+pub struct DaylightPlugin;
+
+impl Plugin for DaylightPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Daylight>()
+            .register_type::<DaylightConfig>()
+            .init_resource::<Daylight>()
+            .init_resource::<DaylightConfig>()
+            .add_systems(FixedUpdate, update_daylight.after(advance_game_time))
+            .add_systems(Update, (tint_grid, apply_darkness_penalty));
+    }
+}
+
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[reflect(Resource)]
+pub enum Daylight {
+    Dawn,
+    #[default]
+    Day,
+    Dusk,
+    Night,
+}
+
+/// Times of day at which each phase starts, and how much each phase slows movement.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct DaylightConfig {
+    pub dawn: Duration,
+    pub day: Duration,
+    pub dusk: Duration,
+    pub night: Duration,
+    pub twilight_movement_penalty: f32,
+    pub night_movement_penalty: f32,
+}
+
+impl Default for DaylightConfig {
+    fn default() -> Self {
+        Self {
+            dawn: Duration::from_secs(5 * 60 * 60),
+            day: Duration::from_secs(7 * 60 * 60),
+            dusk: Duration::from_secs(19 * 60 * 60),
+            night: Duration::from_secs(21 * 60 * 60),
+            twilight_movement_penalty: 1.2,
+            night_movement_penalty: 1.5,
+        }
+    }
+}
+
+impl DaylightConfig {
+    pub fn phase(&self, time_of_day: Duration) -> Daylight {
+        if time_of_day < self.dawn || time_of_day >= self.night {
+            Daylight::Night
+        } else if time_of_day < self.day {
+            Daylight::Dawn
+        } else if time_of_day < self.dusk {
+            Daylight::Day
+        } else {
+            Daylight::Dusk
+        }
+    }
+
+    /// 0 at night, 1 during the day, rising through dawn and falling through dusk.
+    pub fn brightness(&self, time_of_day: Duration) -> f32 {
+        let progress = |from: Duration, to: Duration| {
+            (time_of_day - from).as_secs_f32() / (to - from).as_secs_f32().max(1.0)
+        };
+        match self.phase(time_of_day) {
+            Daylight::Night => 0.0,
+            Daylight::Dawn => progress(self.dawn, self.day),
+            Daylight::Day => 1.0,
+            Daylight::Dusk => 1.0 - progress(self.dusk, self.night),
+        }
+    }
+
+    pub fn movement_penalty(&self, daylight: Daylight) -> Option<f32> {
+        match daylight {
+            Daylight::Day => None,
+            Daylight::Dawn | Daylight::Dusk => Some(self.twilight_movement_penalty),
+            Daylight::Night => Some(self.night_movement_penalty),
+        }
+    }
+}
+
+fn update_daylight(
+    current_time: Res<CurrentTimePoint>,
+    config: Res<DaylightConfig>,
+    mut daylight: ResMut<Daylight>,
+) {
+    let phase = config.phase(current_time.0.time_of_day());
+    if *daylight != phase {
+        *daylight = phase;
+    }
+}
+
+fn tint_grid(
+    current_time: Res<CurrentTimePoint>,
+    config: Res<DaylightConfig>,
+    mut tiles: Query<&mut Sprite, With<HexTile>>,
+    added: Query<(), Added<HexTile>>,
+    mut applied: Local<Option<f32>>,
+) {
+    let brightness = config.brightness(current_time.0.time_of_day());
+    let brightness = (brightness / TINT_STEP).round() * TINT_STEP;
+    if *applied == Some(brightness) && added.is_empty() {
+        return;
+    }
+    *applied = Some(brightness);
+    let tint = NIGHT_TINT.mix(&Color::WHITE, brightness);
+    for mut sprite in tiles.iter_mut() {
+        sprite.color = tint;
+    }
+}
+
+fn apply_darkness_penalty(
+    daylight: Res<Daylight>,
+    config: Res<DaylightConfig>,
+    mut units: Query<&mut MovementPenalties>,
+) {
+    let all_units = daylight.is_changed() || config.is_changed();
+    for mut penalties in units.iter_mut() {
+        if !all_units && !penalties.is_added() {
+            continue;
+        }
+        match config.movement_penalty(*daylight) {
+            Some(value) => penalties.set(MovemenetPenalty {
+                value,
+                reason: MovementPenaltyReason::Darkness,
+            }),
+            None => penalties.remove(MovementPenaltyReason::Darkness),
+        }
+    }
+}
@@ -1,55 +1,227 @@
-use bevy::{app::{App, FixedUpdate, Plugin}, ecs::resource::Resource, time::{Fixed, Time}};
+mod daylight;
+mod scheduler;
+mod speed;
+use std::{
+    ops::{Add, AddAssign, Sub},
+    time::Duration,
+};
+
+use bevy::{
+    app::{App, FixedUpdate, Plugin},
+    ecs::{
+        reflect::ReflectResource,
+        resource::Resource,
+        system::{Res, ResMut},
+    },
+    reflect::Reflect,
+    time::{Fixed, Time},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::time::{
+    daylight::DaylightPlugin, scheduler::GameEventSchedulerPlugin, speed::GameSpeedPlugin,
+};
+
+pub use daylight::{Daylight, DaylightConfig};
+pub use scheduler::{GameEventScheduler, ScheduledEvent, ScheduledEventId, ScheduledEventMessage};
+pub use speed::GameSpeed;
+
+/// Game seconds passing per second of the fixed timestep clock at normal speed.
+pub const GAME_SECONDS_PER_REAL_SECOND: u32 = 60;
 
 pub struct GameTimePlugin;
 
 impl Plugin for GameTimePlugin {
     fn build(&self, app: &mut App) {
-        app
-        .insert_resource(CurrentTimePoint(TimePoint { hours: 8, minutes: 0, seconds: 0, day: 1 }))
-        .add_systems(FixedUpdate, advance_game_time);
+        app.register_type::<GameTime>()
+            .register_type::<CurrentTimePoint>()
+            .insert_resource(CurrentTimePoint(GameTime::new(1, 8, 0)))
+            .add_systems(FixedUpdate, advance_game_time)
+            .add_plugins((GameSpeedPlugin, GameEventSchedulerPlugin, DaylightPlugin));
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq )]
-pub struct TimePoint {
-    pub hours: u32,
-    pub minutes: u32,
-    pub seconds: u32,
-    pub day: u32
+const SECONDS_PER_MINUTE: u64 = 60;
+const SECONDS_PER_HOUR: u64 = 60 * SECONDS_PER_MINUTE;
+const SECONDS_PER_DAY: u64 = 24 * SECONDS_PER_HOUR;
+pub const DAY: Duration = Duration::from_secs(SECONDS_PER_DAY);
+
+/// Point in game time, stored as time elapsed since midnight at the start of day 1.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Reflect,
+    Serialize,
+    Deserialize,
+)]
+pub struct GameTime {
+    elapsed: Duration,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq,Resource )]
-pub struct CurrentTimePoint(pub TimePoint);
+impl GameTime {
+    /// Time at `hour`:`minute` on `day`. Days are numbered from 1.
+    pub fn new(day: u32, hour: u32, minute: u32) -> Self {
+        Self::from_elapsed(Duration::from_secs(
+            day.saturating_sub(1) as u64 * SECONDS_PER_DAY
+                + hour as u64 * SECONDS_PER_HOUR
+                + minute as u64 * SECONDS_PER_MINUTE,
+        ))
+    }
+
+    pub fn from_elapsed(elapsed: Duration) -> Self {
+        Self { elapsed }
+    }
 
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
 
-fn advance_game_time(
-    time: bevy::prelude::Res<Time<Fixed>>,
-    mut current_time: bevy::prelude::ResMut<CurrentTimePoint>,
-) {
-    let delta_seconds = time.delta_secs();
-    let mut total_ingame_seconds = (delta_seconds * 60.0) as u32;
+    pub fn day(&self) -> u32 {
+        (self.elapsed.as_secs() / SECONDS_PER_DAY) as u32 + 1
+    }
 
-    let mut tp = current_time.0;
+    pub fn hour(&self) -> u32 {
+        (self.elapsed.as_secs() % SECONDS_PER_DAY / SECONDS_PER_HOUR) as u32
+    }
 
-    tp.seconds += total_ingame_seconds % 60;
-    if tp.seconds >= 60 {
-        tp.seconds -= 60;
-        tp.minutes += 1;
+    pub fn minute(&self) -> u32 {
+        (self.elapsed.as_secs() % SECONDS_PER_HOUR / SECONDS_PER_MINUTE) as u32
     }
 
-    total_ingame_seconds /= 60;
-    tp.minutes += total_ingame_seconds % 60;
-    if tp.minutes >= 60 {
-        tp.minutes -= 60;
-        tp.hours += 1;
+    pub fn second(&self) -> u32 {
+        (self.elapsed.as_secs() % SECONDS_PER_MINUTE) as u32
     }
 
-    total_ingame_seconds /= 60;
-    tp.hours += total_ingame_seconds % 24;
-    if tp.hours >= 24 {
-        tp.hours -= 24;
-        tp.day += 1;
+    /// First point at or after this one at which the clock shows `time_of_day`.
+    pub fn next_time_of_day(&self, time_of_day: Duration) -> GameTime {
+        let midnight = *self - self.time_of_day();
+        let candidate = midnight + time_of_day;
+        if candidate >= *self {
+            candidate
+        } else {
+            candidate + DAY
+        }
     }
 
-    current_time.0 = tp;
+    /// Time since midnight of the current day.
+    pub fn time_of_day(&self) -> Duration {
+        Duration::new(
+            self.elapsed.as_secs() % SECONDS_PER_DAY,
+            self.elapsed.subsec_nanos(),
+        )
+    }
+}
+
+impl Add<Duration> for GameTime {
+    type Output = GameTime;
+
+    fn add(self, rhs: Duration) -> GameTime {
+        GameTime::from_elapsed(self.elapsed + rhs)
+    }
+}
+
+impl AddAssign<Duration> for GameTime {
+    fn add_assign(&mut self, rhs: Duration) {
+        self.elapsed += rhs;
+    }
+}
+
+impl Sub<Duration> for GameTime {
+    type Output = GameTime;
+
+    fn sub(self, rhs: Duration) -> GameTime {
+        GameTime::from_elapsed(self.elapsed.saturating_sub(rhs))
+    }
+}
+
+impl Sub<GameTime> for GameTime {
+    type Output = Duration;
+
+    /// Time between two points, zero if `rhs` is later than `self`.
+    fn sub(self, rhs: GameTime) -> Duration {
+        self.elapsed.saturating_sub(rhs.elapsed)
+    }
+}
+
+impl std::fmt::Display for GameTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Day {}, {:02}:{:02}:{:02}",
+            self.day(),
+            self.hour(),
+            self.minute(),
+            self.second()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CurrentTimePoint(pub GameTime);
+
+/// Game time passed during the last fixed timestep. Game speed is applied to the fixed clock
+/// itself, so this only converts real time to game time.
+pub fn game_delta(time: &Time<Fixed>) -> Duration {
+    time.delta() * GAME_SECONDS_PER_REAL_SECOND
+}
+
+fn advance_game_time(time: Res<Time<Fixed>>, mut current_time: ResMut<CurrentTimePoint>) {
+    current_time.0 += game_delta(&time);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accessors_roll_over_at_the_end_of_the_day() {
+        let time = GameTime::new(1, 23, 59) + Duration::from_secs(59);
+        assert_eq!(
+            (time.day(), time.hour(), time.minute(), time.second()),
+            (1, 23, 59, 59)
+        );
+
+        let time = time + Duration::from_secs(1);
+        assert_eq!(
+            (time.day(), time.hour(), time.minute(), time.second()),
+            (2, 0, 0, 0)
+        );
+        assert_eq!(time.time_of_day(), Duration::ZERO);
+    }
+
+    #[test]
+    fn minutes_roll_over_into_hours() {
+        let time = GameTime::new(3, 9, 59) + Duration::from_secs(90);
+        assert_eq!(
+            (time.day(), time.hour(), time.minute(), time.second()),
+            (3, 10, 0, 30)
+        );
+        assert_eq!(time.to_string(), "Day 3, 10:00:30");
+    }
+
+    #[test]
+    fn next_time_of_day_moves_to_the_next_day_once_passed() {
+        let dawn = Duration::from_secs(5 * 60 * 60);
+
+        assert_eq!(
+            GameTime::new(1, 4, 0).next_time_of_day(dawn),
+            GameTime::new(1, 5, 0)
+        );
+        assert_eq!(
+            GameTime::new(1, 5, 0).next_time_of_day(dawn),
+            GameTime::new(1, 5, 0)
+        );
+        assert_eq!(
+            GameTime::new(1, 8, 0).next_time_of_day(dawn),
+            GameTime::new(2, 5, 0)
+        );
+    }
 }
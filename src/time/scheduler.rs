@@ -0,0 +1,211 @@
+use std::{cmp::Reverse, collections::BinaryHeap, time::Duration};
+
+use bevy::{
+    app::{App, FixedUpdate, Plugin},
+    ecs::{
+        entity::Entity,
+        message::{Message, MessageWriter},
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, common_conditions::resource_changed},
+        system::{Local, Res, ResMut},
+    },
+    log::debug,
+    platform::collections::HashMap,
+    reflect::Reflect,
+};
+
+use crate::time::{CurrentTimePoint, DAY, DaylightConfig, GameTime, advance_game_time};
+
+pub struct GameEventSchedulerPlugin;
+
+impl Plugin for GameEventSchedulerPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ScheduledEvent>()
+            .init_resource::<GameEventScheduler>()
+            .add_message::<ScheduledEventMessage>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    schedule_daily_orders.run_if(resource_changed::<DaylightConfig>),
+                    fire_scheduled_events,
+                )
+                    .chain()
+                    .after(advance_game_time),
+            );
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+pub enum ScheduledEvent {
+    /// The division commander issues new orders, every day at dawn.
+    DailyOrders,
+    ReconstitutionComplete {
+        unit: Entity,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+pub struct ScheduledEventId(u64);
+
+/// Sent once for every occurrence of a scheduled event the game clock has passed. When a
+/// single time step passes several occurrences, they are sent in chronological order.
+#[derive(Message, Debug, Clone)]
+pub struct ScheduledEventMessage {
+    pub id: ScheduledEventId,
+    pub event: ScheduledEvent,
+    /// When the event was due, which can be slightly earlier than the current time.
+    pub scheduled_for: GameTime,
+}
+
+#[derive(Debug)]
+struct ScheduledEntry {
+    event: ScheduledEvent,
+    period: Option<Duration>,
+}
+
+/// Events to be sent when the game clock reaches a given time.
+#[derive(Resource, Debug, Default)]
+pub struct GameEventScheduler {
+    next_id: u64,
+    entries: HashMap<ScheduledEventId, ScheduledEntry>,
+    queue: BinaryHeap<Reverse<(GameTime, ScheduledEventId)>>,
+}
+
+impl GameEventScheduler {
+    pub fn schedule_once(&mut self, at: GameTime, event: ScheduledEvent) -> ScheduledEventId {
+        self.insert(at, event, None)
+    }
+
+    /// Schedules an event to fire at `first` and then every `period`. A zero period is treated
+    /// as one-shot.
+    pub fn schedule_recurring(
+        &mut self,
+        first: GameTime,
+        period: Duration,
+        event: ScheduledEvent,
+    ) -> ScheduledEventId {
+        let period = (!period.is_zero()).then_some(period);
+        self.insert(first, event, period)
+    }
+
+    /// Returns `true` if the event was still scheduled.
+    pub fn cancel(&mut self, id: ScheduledEventId) -> bool {
+        self.entries.remove(&id).is_some()
+    }
+
+    /// Removes and returns every occurrence due at or before `now`, earliest first.
+    pub fn drain_due(&mut self, now: GameTime) -> Vec<ScheduledEventMessage> {
+        let mut due = Vec::new();
+        while let Some(&Reverse((at, id))) = self.queue.peek() {
+            if at > now {
+                break;
+            }
+            self.queue.pop();
+            // Cancelled events stay in the queue until they come up.
+            let Some(entry) = self.entries.get(&id) else {
+                continue;
+            };
+            let period = entry.period;
+            due.push(ScheduledEventMessage {
+                id,
+                event: entry.event.clone(),
+                scheduled_for: at,
+            });
+            match period {
+                Some(period) => self.queue.push(Reverse((at + period, id))),
+                None => {
+                    self.entries.remove(&id);
+                }
+            }
+        }
+        due
+    }
+
+    fn insert(
+        &mut self,
+        at: GameTime,
+        event: ScheduledEvent,
+        period: Option<Duration>,
+    ) -> ScheduledEventId {
+        let id = ScheduledEventId(self.next_id);
+        self.next_id += 1;
+        self.entries.insert(id, ScheduledEntry { event, period });
+        self.queue.push(Reverse((at, id)));
+        id
+    }
+}
+
+/// Moves the daily orders along with dawn whenever the daylight times change.
+fn schedule_daily_orders(
+    mut scheduler: ResMut<GameEventScheduler>,
+    current_time: Res<CurrentTimePoint>,
+    config: Res<DaylightConfig>,
+    mut scheduled: Local<Option<ScheduledEventId>>,
+) {
+    if let Some(id) = scheduled.take() {
+        scheduler.cancel(id);
+    }
+    *scheduled = Some(scheduler.schedule_recurring(
+        current_time.0.next_time_of_day(config.dawn),
+        DAY,
+        ScheduledEvent::DailyOrders,
+    ));
+}
+
+fn fire_scheduled_events(
+    mut scheduler: ResMut<GameEventScheduler>,
+    current_time: Res<CurrentTimePoint>,
+    mut messages: MessageWriter<ScheduledEventMessage>,
+) {
+    for message in scheduler.drain_due(current_time.0) {
+        debug!(
+            "Scheduled event {:?} due at {} fired at {}",
+            message.event, message.scheduled_for, current_time.0
+        );
+        messages.write(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_step_fires_every_due_event_in_order() {
+        let mut scheduler = GameEventScheduler::default();
+        let unit = Entity::from_raw_u32(1).unwrap();
+        let reconstituted = scheduler.schedule_once(
+            GameTime::new(1, 14, 0),
+            ScheduledEvent::ReconstitutionComplete { unit },
+        );
+        let daily =
+            scheduler.schedule_recurring(GameTime::new(1, 5, 0), DAY, ScheduledEvent::DailyOrders);
+        scheduler.schedule_once(GameTime::new(3, 0, 0), ScheduledEvent::DailyOrders);
+
+        let due = scheduler.drain_due(GameTime::new(2, 12, 0));
+        let fired: Vec<_> = due
+            .iter()
+            .map(|message| (message.id, message.scheduled_for))
+            .collect();
+        assert_eq!(
+            fired,
+            vec![
+                (daily, GameTime::new(1, 5, 0)),
+                (reconstituted, GameTime::new(1, 14, 0)),
+                (daily, GameTime::new(2, 5, 0)),
+            ]
+        );
+        assert!(scheduler.drain_due(GameTime::new(2, 12, 0)).is_empty());
+    }
+
+    #[test]
+    fn cancelled_events_do_not_fire() {
+        let mut scheduler = GameEventScheduler::default();
+        let id =
+            scheduler.schedule_recurring(GameTime::new(1, 5, 0), DAY, ScheduledEvent::DailyOrders);
+
+        assert!(scheduler.cancel(id));
+        assert!(scheduler.drain_due(GameTime::new(5, 0, 0)).is_empty());
+        assert!(!scheduler.cancel(id));
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        reflect::ReflectResource,
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, SystemCondition, common_conditions::resource_changed},
+        system::{Local, Res, ResMut, Single},
+    },
+    reflect::Reflect,
+    state::{
+        condition::in_state,
+        state::{NextState, OnEnter, OnExit, State},
+    },
+    time::{Time, Virtual},
+};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{GameState, game_actions::GameActions};
+
+/// Game speed is applied by scaling virtual time, which drives the fixed timestep all
+/// simulation systems run on. Pausing stops virtual time, so no fixed updates run at all.
+pub struct GameSpeedPlugin;
+
+impl Plugin for GameSpeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<GameSpeed>()
+            .init_resource::<GameSpeed>()
+            .add_systems(
+                Update,
+                (
+                    change_game_speed
+                        .run_if(in_state(GameState::Running).or(in_state(GameState::Paused))),
+                    apply_game_speed.run_if(resource_changed::<GameSpeed>),
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(GameState::Paused), pause_virtual_time)
+            .add_systems(OnExit(GameState::Paused), unpause_virtual_time);
+    }
+}
+
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[reflect(Resource)]
+pub enum GameSpeed {
+    Paused,
+    #[default]
+    Normal,
+    Double,
+    Fast,
+    VeryFast,
+}
+
+impl GameSpeed {
+    pub fn multiplier(&self) -> f32 {
+        match self {
+            GameSpeed::Paused => 0.0,
+            GameSpeed::Normal => 1.0,
+            GameSpeed::Double => 2.0,
+            GameSpeed::Fast => 5.0,
+            GameSpeed::VeryFast => 10.0,
+        }
+    }
+
+    pub fn faster(&self) -> Self {
+        match self {
+            GameSpeed::Paused => GameSpeed::Normal,
+            GameSpeed::Normal => GameSpeed::Double,
+            GameSpeed::Double => GameSpeed::Fast,
+            GameSpeed::Fast | GameSpeed::VeryFast => GameSpeed::VeryFast,
+        }
+    }
+
+    pub fn slower(&self) -> Self {
+        match self {
+            GameSpeed::Paused => GameSpeed::Paused,
+            GameSpeed::Normal | GameSpeed::Double => GameSpeed::Normal,
+            GameSpeed::Fast => GameSpeed::Double,
+            GameSpeed::VeryFast => GameSpeed::Fast,
+        }
+    }
+}
+
+fn change_game_speed(
+    input: Single<&ActionState<GameActions>>,
+    mut speed: ResMut<GameSpeed>,
+    mut speed_before_pause: Local<Option<GameSpeed>>,
+) {
+    if input.just_pressed(&GameActions::TogglePause) {
+        if *speed == GameSpeed::Paused {
+            *speed = speed_before_pause.take().unwrap_or_default();
+        } else {
+            *speed_before_pause = Some(*speed);
+            *speed = GameSpeed::Paused;
+        }
+    }
+    if input.just_pressed(&GameActions::IncreaseSpeed) {
+        *speed = speed.faster();
+    }
+    if input.just_pressed(&GameActions::DecreaseSpeed) {
+        *speed = speed.slower();
+    }
+}
+
+fn apply_game_speed(
+    speed: Res<GameSpeed>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    match (*speed, state.get()) {
+        (GameSpeed::Paused, GameState::Running) => next_state.set(GameState::Paused),
+        (GameSpeed::Paused, _) => {}
+        (speed, current) => {
+            time.set_relative_speed(speed.multiplier());
+            if *current == GameState::Paused {
+                next_state.set(GameState::Running);
+            }
+        }
+    }
+}
+
+fn pause_virtual_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn unpause_virtual_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
@@ -0,0 +1,95 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        entity::Entity,
+        message::MessageWriter,
+        query::With,
+        system::{Query, Res, Single},
+    },
+};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    game_actions::GameActions,
+    unit_managment::{SelectUnitMessage, SelectedUnitList, SelectionMode},
+    units::{AttachUnitMessage, AttachedTo, CompositeUnit, DetachUnitMessage, Unit},
+};
+
+/// Reorganizes the selected units within the unit hierarchy.
+pub struct HierarchyCommandsPlugin;
+
+impl Plugin for HierarchyCommandsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                select_superiors,
+                detach_selected_units,
+                attach_selected_units,
+            ),
+        );
+    }
+}
+
+/// Replaces the selection with the direct superiors of the selected units.
+fn select_superiors(
+    input: Single<&ActionState<GameActions>>,
+    selected_units: Res<SelectedUnitList>,
+    attachments: Query<&AttachedTo>,
+    mut writer: MessageWriter<SelectUnitMessage>,
+) {
+    if !input.just_pressed(&GameActions::SelectSuperior) {
+        return;
+    }
+    let mut superiors = Vec::new();
+    for attached_to in attachments.iter_many(selected_units.units()) {
+        if !superiors.contains(&attached_to.0) {
+            superiors.push(attached_to.0);
+        }
+    }
+    if !superiors.is_empty() {
+        writer.write(SelectUnitMessage {
+            units: superiors,
+            mode: SelectionMode::Replace,
+        });
+    }
+}
+
+fn detach_selected_units(
+    input: Single<&ActionState<GameActions>>,
+    selected_units: Res<SelectedUnitList>,
+    attachments: Query<(), With<AttachedTo>>,
+    mut writer: MessageWriter<DetachUnitMessage>,
+) {
+    if !input.just_pressed(&GameActions::DetachUnits) {
+        return;
+    }
+    for &unit in selected_units.units() {
+        if attachments.contains(unit) {
+            writer.write(DetachUnitMessage { unit });
+        }
+    }
+}
+
+/// Attaches the selected units to the selected composite unit of the highest echelon.
+fn attach_selected_units(
+    input: Single<&ActionState<GameActions>>,
+    selected_units: Res<SelectedUnitList>,
+    composites: Query<(Entity, &Unit), With<CompositeUnit>>,
+    mut writer: MessageWriter<AttachUnitMessage>,
+) {
+    if !input.just_pressed(&GameActions::AttachUnits) {
+        return;
+    }
+    let Some((superior, _)) = composites
+        .iter_many(selected_units.units())
+        .max_by_key(|(_, unit)| unit.echelon)
+    else {
+        return;
+    };
+    for &unit in selected_units.units() {
+        if unit != superior {
+            writer.write(AttachUnitMessage { unit, superior });
+        }
+    }
+}
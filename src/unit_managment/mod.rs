@@ -1,15 +1,15 @@
+pub mod hierarchy;
 pub mod orders;
 pub mod selection;
 use bevy::{
-    app::{App, Plugin, Update},
-    ecs::{
-        entity::Entity, event::{Event, EventReader}, message::{Message, MessageReader}, resource::Resource, schedule::{IntoScheduleConfigs, common_conditions}, system::ResMut
-    },
+    app::{App, Plugin},
+    ecs::{entity::Entity, message::Message, resource::Resource},
     reflect::Reflect,
 };
-use bevy_inspector_egui::quick::ResourceInspectorPlugin;
 
-use crate::unit_managment::{orders::OrdersPlugin, selection::SelectionPlugin};
+use crate::unit_managment::{
+    hierarchy::HierarchyCommandsPlugin, orders::OrdersPlugin, selection::SelectionPlugin,
+};
 
 pub struct UnitManagementPlugin;
 
@@ -17,34 +17,46 @@ impl Plugin for UnitManagementPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(OrdersPlugin)
             .add_plugins(SelectionPlugin)
-            .add_event::<SelectUnitMessage>()
+            .add_plugins(HierarchyCommandsPlugin)
+            .add_message::<SelectUnitMessage>()
             .register_type::<SelectUnitMessage>()
             .insert_resource(SelectedUnitList::default())
-            .register_type::<SelectedUnitList>()
-            .add_systems(
-                Update,
-                add_units_to_selection.run_if(common_conditions::on_event::<SelectUnitMessage>),
-            );
+            .register_type::<SelectedUnitList>();
     }
 }
 
-#[derive(Debug, Reflect, Message)]
-pub struct SelectUnitMessage {
-    pub unit: Entity,
+/// How units named by a [`SelectUnitMessage`] are combined with the current selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum SelectionMode {
+    /// Select exactly the given units. With no units this clears the selection.
+    #[default]
+    Replace,
+    Add,
+    /// Select the given units which are not selected and deselect the ones which are.
+    Toggle,
 }
 
-fn add_units_to_selection(
-    mut selected_units: ResMut<SelectedUnitList>,
-    mut select_unit_events: MessageReader<SelectUnitMessage>,
-) {
-    for event in select_unit_events.read() {
-        if !selected_units.selected_units.contains(&event.unit) {
-            selected_units.selected_units.push(event.unit);
-        }
-    }
+#[derive(Debug, Reflect, Message)]
+pub struct SelectUnitMessage {
+    pub units: Vec<Entity>,
+    pub mode: SelectionMode,
 }
 
 #[derive(Resource, Reflect, Default, Debug)]
 pub struct SelectedUnitList {
     selected_units: Vec<Entity>,
 }
+
+impl SelectedUnitList {
+    pub fn units(&self) -> &[Entity] {
+        &self.selected_units
+    }
+
+    pub fn contains(&self, unit: Entity) -> bool {
+        self.selected_units.contains(&unit)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selected_units.is_empty()
+    }
+}
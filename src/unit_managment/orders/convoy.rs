@@ -0,0 +1,280 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    camera::visibility::Visibility,
+    ecs::{
+        entity::Entity,
+        message::{MessageReader, MessageWriter},
+        query::With,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, Single},
+    },
+    log::warn,
+};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    game_actions::GameActions,
+    movement::{GamePosition, MovingTowards, Path},
+    resources::{PalletizationConfig, ResourceTypes},
+    unit_managment::{
+        SelectedUnitList,
+        orders::{
+            ClearOrdersMessage, IssueOrderMessage, Order, OrderFailure, OrderFinishedMessage,
+            OrderState, OrderStateChangedMessage, OrderSystems, update_order_queues,
+        },
+    },
+    units::{
+        Convoy, ConvoyBundle, ConvoyMember, MIN_CONVOY_SIZE, SupplyStorage, Unit, UnitDetails,
+        UnitTypeList, combined_consumption, combined_manifest, combined_movement_stats,
+    },
+};
+
+/// Carries out orders forming and disbanding convoys.
+pub struct ConvoyOrdersPlugin;
+
+impl Plugin for ConvoyOrdersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            issue_convoy_orders
+                .in_set(OrderSystems::Queue)
+                .before(update_order_queues),
+        )
+        .add_systems(Update, execute_convoy_orders.in_set(OrderSystems::Execute));
+    }
+}
+
+/// Forms the selected vehicles into a convoy led by the first of them, or disbands the
+/// selected convoys.
+fn issue_convoy_orders(
+    input: Single<&ActionState<GameActions>>,
+    selected_units: Res<SelectedUnitList>,
+    convoys: Query<(), With<Convoy>>,
+    mut issued: MessageWriter<IssueOrderMessage>,
+) {
+    let queued = input.pressed(&GameActions::QueueOrder);
+    if input.just_pressed(&GameActions::FormConvoy)
+        && let Some((&lead, members)) = selected_units.units().split_first()
+    {
+        issued.write(IssueOrderMessage {
+            unit: lead,
+            order: Order::FormConvoy {
+                members: members.to_vec(),
+            },
+            queued,
+        });
+    }
+    if input.just_pressed(&GameActions::DisbandConvoy) {
+        for &unit in selected_units.units() {
+            if convoys.contains(unit) {
+                issued.write(IssueOrderMessage {
+                    unit,
+                    order: Order::DisbandConvoy,
+                    queued,
+                });
+            }
+        }
+    }
+}
+
+fn execute_convoy_orders(
+    mut transitions: MessageReader<OrderStateChangedMessage>,
+    units: Query<(&Unit, Option<&GamePosition>, Option<&ConvoyMember>)>,
+    convoys: Query<(&Convoy, &GamePosition)>,
+    mut storages: Query<&mut SupplyStorage>,
+    unit_types: Res<UnitTypeList>,
+    resource_types: Res<ResourceTypes>,
+    config: Res<PalletizationConfig>,
+    mut issued: MessageWriter<IssueOrderMessage>,
+    mut finished: MessageWriter<OrderFinishedMessage>,
+    mut cleared: MessageWriter<ClearOrdersMessage>,
+    mut commands: Commands,
+) {
+    for message in transitions.read() {
+        if message.state != OrderState::Active {
+            continue;
+        }
+        let result = match &message.order {
+            Order::FormConvoy { members } => {
+                let mut vehicles = vec![message.unit];
+                for member in members {
+                    if !vehicles.contains(member) {
+                        vehicles.push(*member);
+                    }
+                }
+                form_convoy(
+                    vehicles,
+                    &units,
+                    &mut storages,
+                    &unit_types,
+                    &mut issued,
+                    &mut commands,
+                )
+            }
+            Order::DisbandConvoy => {
+                let Ok((convoy, position)) = convoys.get(message.unit) else {
+                    finished.write(OrderFinishedMessage {
+                        unit: message.unit,
+                        id: message.id,
+                        result: Err(OrderFailure::NotCapable),
+                    });
+                    continue;
+                };
+                let members: Vec<Entity> = convoy
+                    .members
+                    .iter()
+                    .copied()
+                    .filter(|&member| units.contains(member))
+                    .collect();
+                if !split_manifest(
+                    message.unit,
+                    &members,
+                    &mut storages,
+                    &resource_types,
+                    &config,
+                ) {
+                    warn!(
+                        "Supplies in convoy {:?} cannot be split between its members",
+                        message.unit
+                    );
+                    finished.write(OrderFinishedMessage {
+                        unit: message.unit,
+                        id: message.id,
+                        result: Err(OrderFailure::NoRoom),
+                    });
+                    continue;
+                }
+                for &member in members.iter() {
+                    commands
+                        .entity(member)
+                        .insert((GamePosition { hex: position.hex }, Visibility::Inherited))
+                        .remove::<ConvoyMember>();
+                    // Ends the open-ended hold members wait on while in the convoy, and
+                    // anything else still queued for them.
+                    cleared.write(ClearOrdersMessage {
+                        unit: member,
+                        active: true,
+                    });
+                }
+                commands.entity(message.unit).despawn();
+                Ok(())
+            }
+            _ => continue,
+        };
+        finished.write(OrderFinishedMessage {
+            unit: message.unit,
+            id: message.id,
+            result,
+        });
+    }
+}
+
+/// Takes `vehicles` off the map into a new convoy. The first vehicle's hex is where the convoy
+/// is formed, every other vehicle has to be there as well.
+fn form_convoy(
+    vehicles: Vec<Entity>,
+    units: &Query<(&Unit, Option<&GamePosition>, Option<&ConvoyMember>)>,
+    storages: &mut Query<&mut SupplyStorage>,
+    unit_types: &UnitTypeList,
+    issued: &mut MessageWriter<IssueOrderMessage>,
+    commands: &mut Commands,
+) -> Result<(), OrderFailure> {
+    if vehicles.len() < MIN_CONVOY_SIZE {
+        return Err(OrderFailure::Invalid);
+    }
+    let mut details: Vec<&UnitDetails> = Vec::new();
+    let mut hex = None;
+    for &vehicle in vehicles.iter() {
+        let Ok((unit, position, member)) = units.get(vehicle) else {
+            return Err(OrderFailure::TargetLost);
+        };
+        if member.is_some() {
+            return Err(OrderFailure::Invalid);
+        }
+        let Some(vehicle_details) = unit_types
+            .types
+            .get(&unit.unit_type)
+            .filter(|details| details.vehicle)
+        else {
+            return Err(OrderFailure::NotCapable);
+        };
+        let Some(position) = position else {
+            return Err(OrderFailure::NotInSameHex);
+        };
+        if *hex.get_or_insert(position.hex) != position.hex {
+            return Err(OrderFailure::NotInSameHex);
+        }
+        details.push(vehicle_details);
+    }
+    let Some(hex) = hex else {
+        return Err(OrderFailure::Invalid);
+    };
+
+    let mut manifest = combined_manifest(details.iter().map(|details| details.supply_storage));
+    for &vehicle in vehicles.iter() {
+        if let Ok(mut storage) = storages.get_mut(vehicle) {
+            for stack in std::mem::take(&mut storage.storage) {
+                manifest.insert(stack);
+            }
+        }
+    }
+    let convoy = commands
+        .spawn(ConvoyBundle::new(
+            hex,
+            vehicles.clone(),
+            combined_movement_stats(details.iter().map(|details| &details.movement_stats)),
+            combined_consumption(details.iter().map(|details| &details.consumption)),
+            manifest,
+        ))
+        .id();
+    for vehicle in vehicles {
+        commands
+            .entity(vehicle)
+            .insert((ConvoyMember { convoy }, Visibility::Hidden))
+            .remove::<(GamePosition, MovingTowards, Path)>();
+        // Drops whatever the vehicle was doing until the convoy is disbanded.
+        issued.write(IssueOrderMessage {
+            unit: vehicle,
+            order: Order::Hold { until: None },
+            queued: false,
+        });
+    }
+    Ok(())
+}
+
+/// Moves the convoy's manifest into its members, each taking as much as it can carry. If
+/// something is left over the members' share goes back into the manifest, so no supplies are
+/// lost, and `false` is returned.
+fn split_manifest(
+    convoy: Entity,
+    members: &[Entity],
+    storages: &mut Query<&mut SupplyStorage>,
+    resource_types: &ResourceTypes,
+    config: &PalletizationConfig,
+) -> bool {
+    for &member in members {
+        let Ok([mut manifest, mut storage]) = storages.get_many_mut([convoy, member]) else {
+            continue;
+        };
+        for stack in manifest.storage.clone() {
+            let _ = manifest.transfer(&mut storage, stack, resource_types, config);
+        }
+    }
+    if storages
+        .get(convoy)
+        .is_ok_and(|manifest| manifest.storage.is_empty())
+    {
+        return true;
+    }
+    // Members carry nothing of their own while in a convoy, everything they hold came from
+    // the manifest.
+    for &member in members {
+        let Ok([mut manifest, mut storage]) = storages.get_many_mut([convoy, member]) else {
+            continue;
+        };
+        for stack in std::mem::take(&mut storage.storage) {
+            manifest.insert(stack);
+        }
+    }
+    false
+}
@@ -0,0 +1,126 @@
+use bevy::{ecs::entity::Entity, platform::collections::HashSet};
+use hexx::Hex;
+
+/// Groups spread wider than this, in hexes from their center, are gathered around the
+/// destination instead of keeping their relative positions.
+const MAX_FORMATION_RADIUS: i32 = 4;
+/// How far from its preferred hex a unit may be placed before it is sent to the preferred hex
+/// anyway.
+const MAX_DISPLACEMENT: u32 = 6;
+
+/// Picks a distinct destination for every unit of a group ordered to move to `target`.
+///
+/// Units keep their offsets from the group's center where possible. A unit whose preferred
+/// hex is taken or cannot be entered (`can_enter` returns `false`) gets the closest free hex
+/// instead.
+pub fn group_destinations(
+    units: &[(Entity, Hex)],
+    target: Hex,
+    mut can_enter: impl FnMut(Entity, Hex) -> bool,
+) -> Vec<(Entity, Hex)> {
+    if units.is_empty() {
+        return Vec::new();
+    }
+    let count = units.len() as f32;
+    let (x, y) = units.iter().fold((0.0, 0.0), |(x, y), (_, hex)| {
+        (x + hex.x as f32, y + hex.y as f32)
+    });
+    let center = Hex::round([x / count, y / count]);
+    let keep_formation = units
+        .iter()
+        .all(|(_, hex)| (*hex - center).length() <= MAX_FORMATION_RADIUS);
+
+    // Units closest to the center pick first, so the core of the group ends up on target.
+    let mut ordered: Vec<(Entity, Hex)> = units.to_vec();
+    ordered.sort_by_key(|(_, hex)| (*hex - center).length());
+
+    let mut taken = HashSet::new();
+    ordered
+        .into_iter()
+        .map(|(unit, hex)| {
+            let preferred = if keep_formation {
+                target + (hex - center)
+            } else {
+                target
+            };
+            let destination = preferred
+                .spiral_range(0..=MAX_DISPLACEMENT)
+                .find(|&candidate| !taken.contains(&candidate) && can_enter(unit, candidate))
+                .unwrap_or(preferred);
+            taken.insert(destination);
+            (unit, destination)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn units(hexes: &[Hex]) -> Vec<(Entity, Hex)> {
+        hexes
+            .iter()
+            .enumerate()
+            .map(|(index, &hex)| (Entity::from_raw_u32(index as u32).unwrap(), hex))
+            .collect()
+    }
+
+    fn destination_of(destinations: &[(Entity, Hex)], unit: Entity) -> Hex {
+        destinations
+            .iter()
+            .find(|(entity, _)| *entity == unit)
+            .map(|(_, hex)| *hex)
+            .unwrap()
+    }
+
+    #[test]
+    fn close_group_keeps_its_offsets() {
+        let group = units(&[
+            Hex::new(0, 0),
+            Hex::new(1, 0),
+            Hex::new(-1, 0),
+            Hex::new(0, 1),
+            Hex::new(0, -1),
+        ]);
+        let target = Hex::new(5, 5);
+
+        let destinations = group_destinations(&group, target, |_, _| true);
+
+        for &(unit, hex) in group.iter() {
+            assert_eq!(destination_of(&destinations, unit), target + hex);
+        }
+    }
+
+    #[test]
+    fn spread_out_group_gathers_on_distinct_hexes_around_the_target() {
+        let group = units(&[Hex::new(0, 0), Hex::new(10, 0), Hex::new(-10, 0)]);
+        let target = Hex::new(5, 5);
+
+        let destinations = group_destinations(&group, target, |_, _| true);
+
+        assert_eq!(destination_of(&destinations, group[0].0), target);
+        let hexes: HashSet<Hex> = destinations.iter().map(|(_, hex)| *hex).collect();
+        assert_eq!(hexes.len(), group.len());
+        assert!(
+            hexes
+                .iter()
+                .all(|hex| hex.unsigned_distance_to(target) <= 1)
+        );
+    }
+
+    #[test]
+    fn crowded_target_sends_units_to_the_closest_free_hexes() {
+        let group = units(&[Hex::new(0, 0), Hex::new(10, 0)]);
+        let target = Hex::new(5, 5);
+
+        let destinations = group_destinations(&group, target, |_, hex| hex != target);
+
+        let hexes: HashSet<Hex> = destinations.iter().map(|(_, hex)| *hex).collect();
+        assert_eq!(hexes.len(), group.len());
+        assert!(
+            hexes
+                .iter()
+                .all(|hex| hex.unsigned_distance_to(target) == 1)
+        );
+    }
+}
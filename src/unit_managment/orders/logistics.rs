@@ -0,0 +1,183 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        entity::Entity,
+        message::{MessageReader, MessageWriter},
+        query::With,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res},
+    },
+};
+
+use crate::{
+    movement::GamePosition,
+    resources::{PalletizationConfig, ResourceStack, ResourceTypes},
+    unit_managment::orders::{
+        Order, OrderFailure, OrderFinishedMessage, OrderQueue, OrderState,
+        OrderStateChangedMessage, OrderSystems,
+    },
+    units::{
+        SupplyStorage, Transloading, TransloadingCompletedMessage, TransloadingError, Unit,
+        UnitTypeList,
+    },
+};
+
+/// Carries out load, unload and transload orders.
+pub struct LogisticsOrdersPlugin;
+
+impl Plugin for LogisticsOrdersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (execute_supply_transfer_orders, execute_transload_orders)
+                .in_set(OrderSystems::Execute),
+        );
+    }
+}
+
+fn execute_supply_transfer_orders(
+    mut transitions: MessageReader<OrderStateChangedMessage>,
+    mut storages: Query<(&mut SupplyStorage, Option<&GamePosition>)>,
+    resource_types: Res<ResourceTypes>,
+    config: Res<PalletizationConfig>,
+    mut finished: MessageWriter<OrderFinishedMessage>,
+) {
+    for message in transitions.read() {
+        if message.state != OrderState::Active {
+            continue;
+        }
+        let result = match message.order {
+            Order::Load { storage, stack } => transfer(
+                storage,
+                message.unit,
+                stack,
+                &mut storages,
+                &resource_types,
+                &config,
+            ),
+            Order::Unload { storage, stack } => transfer(
+                message.unit,
+                storage,
+                stack,
+                &mut storages,
+                &resource_types,
+                &config,
+            ),
+            _ => continue,
+        };
+        finished.write(OrderFinishedMessage {
+            unit: message.unit,
+            id: message.id,
+            result,
+        });
+    }
+}
+
+/// Moves as much of `stack` as possible between two storages in the same hex.
+fn transfer(
+    from: Entity,
+    to: Entity,
+    stack: ResourceStack,
+    storages: &mut Query<(&mut SupplyStorage, Option<&GamePosition>)>,
+    resource_types: &ResourceTypes,
+    config: &PalletizationConfig,
+) -> Result<(), OrderFailure> {
+    let Ok([(mut source, source_position), (mut target, target_position)]) =
+        storages.get_many_mut([from, to])
+    else {
+        return Err(OrderFailure::TargetLost);
+    };
+    if let (Some(source_position), Some(target_position)) = (source_position, target_position)
+        && source_position.hex != target_position.hex
+    {
+        return Err(OrderFailure::NotInSameHex);
+    }
+    let moved = source
+        .transfer(&mut target, stack, resource_types, config)
+        .map_err(OrderFailure::Supply)?;
+    if moved.amount.is_empty() {
+        return Err(OrderFailure::NothingTransferred);
+    }
+    Ok(())
+}
+
+fn execute_transload_orders(
+    mut transitions: MessageReader<OrderStateChangedMessage>,
+    mut completed: MessageReader<TransloadingCompletedMessage>,
+    queues: Query<&OrderQueue>,
+    units: Query<(&Unit, &GamePosition)>,
+    storages: Query<Option<&GamePosition>, With<SupplyStorage>>,
+    unit_types: Res<UnitTypeList>,
+    mut finished: MessageWriter<OrderFinishedMessage>,
+    mut commands: Commands,
+) {
+    for message in transitions.read() {
+        let Order::Transload { storage } = message.order else {
+            continue;
+        };
+        match message.state {
+            OrderState::Active => {
+                let unit = units.get(message.unit).ok();
+                let capable = unit.is_some_and(|(unit, _)| {
+                    unit_types
+                        .types
+                        .get(&unit.unit_type)
+                        .is_some_and(|details| details.transloading_rate.is_some())
+                });
+                let storage_position = storages.get(storage);
+                let failure = if !capable {
+                    Some(OrderFailure::NotCapable)
+                } else if storage_position.is_err() {
+                    Some(OrderFailure::TargetLost)
+                } else if let (Some((_, position)), Ok(Some(storage_position))) =
+                    (unit, storage_position)
+                    && storage_position.hex != position.hex
+                {
+                    Some(OrderFailure::NotInSameHex)
+                } else {
+                    None
+                };
+                match failure {
+                    Some(failure) => {
+                        finished.write(OrderFinishedMessage {
+                            unit: message.unit,
+                            id: message.id,
+                            result: Err(failure),
+                        });
+                    }
+                    None => {
+                        commands
+                            .entity(message.unit)
+                            .insert(Transloading::new(storage));
+                    }
+                }
+            }
+            OrderState::Cancelled => {
+                commands.entity(message.unit).remove::<Transloading>();
+            }
+            _ => {}
+        }
+    }
+
+    for message in completed.read() {
+        let Some((id, order)) = queues.get(message.unit).ok().and_then(OrderQueue::active) else {
+            continue;
+        };
+        if *order
+            == (Order::Transload {
+                storage: message.storage,
+            })
+        {
+            finished.write(OrderFinishedMessage {
+                unit: message.unit,
+                id,
+                result: message.result.map_err(|error| match error {
+                    TransloadingError::StorageLost => OrderFailure::TargetLost,
+                    TransloadingError::NotCapable => OrderFailure::NotCapable,
+                    TransloadingError::NotInSameHex => OrderFailure::NotInSameHex,
+                    TransloadingError::NoRoom => OrderFailure::NoRoom,
+                }),
+            });
+        }
+    }
+}
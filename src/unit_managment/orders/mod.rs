@@ -0,0 +1,443 @@
+mod convoy;
+mod group;
+mod logistics;
+mod movement;
+mod reconstitution;
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{
+    app::{Plugin, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        message::{Message, MessageReader, MessageWriter},
+        query::With,
+        reflect::ReflectResource,
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, SystemSet, common_conditions},
+        system::{Query, Res, Single},
+    },
+    log::debug,
+    reflect::Reflect,
+};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    game_actions::GameActions,
+    map::{HexGrid, HexPosition, PathFindingError},
+    movement::{DifficultTerrain, GamePosition, Kph, MovementConfig, MovementStats},
+    resources::ResourceStack,
+    time::GameTime,
+    unit_managment::{
+        SelectedUnitList,
+        orders::{
+            convoy::ConvoyOrdersPlugin, group::group_destinations,
+            logistics::LogisticsOrdersPlugin, movement::MovementOrdersPlugin,
+            reconstitution::ReconstitutionOrdersPlugin,
+        },
+    },
+    units::{
+        ConvoyMember, HexOccupancy, Subordinates, SupplyError, Unit, UnitTypeList, atomic_units,
+    },
+};
+
+/// How orders given to several units at once are carried out.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct GroupMoveConfig {
+    /// Move every unit of the group at the base speed of its slowest member.
+    pub match_slowest_pace: bool,
+}
+
+/// Orders are queued and moved through their lifecycle by the systems in
+/// [`OrderSystems::Queue`]. Each order kind is carried out by its own systems in
+/// [`OrderSystems::Execute`], which react to [`OrderStateChangedMessage`] and report back with
+/// [`OrderFinishedMessage`].
+pub struct OrdersPlugin;
+
+impl Plugin for OrdersPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_message::<MoveOrderIssuedMessage>()
+            .add_message::<IssueOrderMessage>()
+            .add_message::<ClearOrdersMessage>()
+            .add_message::<OrderFinishedMessage>()
+            .add_message::<OrderStateChangedMessage>()
+            .register_type::<MoveOrderIssuedMessage>()
+            .register_type::<Order>()
+            .register_type::<OrderId>()
+            .register_type::<OrderQueue>()
+            .register_type::<GroupMoveConfig>()
+            .init_resource::<GroupMoveConfig>()
+            .configure_sets(Update, OrderSystems::Queue.before(OrderSystems::Execute))
+            .add_systems(
+                Update,
+                (
+                    issue_move_order
+                        .run_if(common_conditions::on_message::<MoveOrderIssuedMessage>),
+                    clear_selected_orders,
+                    update_order_queues,
+                )
+                    .chain()
+                    .in_set(OrderSystems::Queue),
+            )
+            .add_plugins((
+                MovementOrdersPlugin,
+                LogisticsOrdersPlugin,
+                ReconstitutionOrdersPlugin,
+                ConvoyOrdersPlugin,
+            ));
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderSystems {
+    Queue,
+    Execute,
+}
+
+#[derive(Debug, Reflect, Message)]
+pub struct MoveOrderIssuedMessage {
+    pub destination: HexPosition,
+    /// Append to the order queue instead of replacing it.
+    pub queued: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub enum Order {
+    Move {
+        destination: HexPosition,
+        /// Speed the unit will not exceed, used to keep a group together.
+        max_speed: Option<Kph>,
+    },
+    /// Stay in place, until given time or until replaced by another order.
+    Hold {
+        until: Option<GameTime>,
+    },
+    /// Take supplies from a storage in the same hex.
+    Load {
+        storage: Entity,
+        stack: ResourceStack,
+    },
+    /// Hand supplies over to a storage in the same hex.
+    Unload {
+        storage: Entity,
+        stack: ResourceStack,
+    },
+    /// Break down pallets held by a storage, see [`crate::units::Transloading`].
+    Transload {
+        storage: Entity,
+    },
+    /// Follow another unit until it is gone or the order is replaced.
+    Escort {
+        target: Entity,
+    },
+    /// Move between at least two waypoints in a loop until the order is replaced.
+    Patrol {
+        waypoints: Vec<HexPosition>,
+    },
+    Reconstitute {
+        duration: Duration,
+    },
+    /// Form a convoy with the given vehicles, all of which have to be in this unit's hex.
+    FormConvoy {
+        members: Vec<Entity>,
+    },
+    /// Release the vehicles of this convoy in its current hex. Fails without releasing them if
+    /// they cannot carry the convoy's supplies between them.
+    DisbandConvoy,
+}
+
+impl Order {
+    /// Hex the unit ends up in once the order is carried out, for orders which move the unit.
+    pub fn destination(&self) -> Option<HexPosition> {
+        match self {
+            Order::Move { destination, .. } => Some(*destination),
+            Order::Patrol { waypoints } => waypoints.first().copied(),
+            _ => None,
+        }
+    }
+}
+
+/// Identifies an order within the queue of a single unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub struct OrderId(u64);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderState {
+    /// Waiting in the queue.
+    Pending,
+    Active,
+    Completed,
+    Failed(OrderFailure),
+    /// Dropped from the queue before it finished.
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderFailure {
+    PathNotFound(PathFindingError),
+    /// The unit or storage the order refers to no longer exists.
+    TargetLost,
+    /// The unit has to be in the same hex as the storage.
+    NotInSameHex,
+    NothingTransferred,
+    Supply(SupplyError),
+    /// The unit's type cannot carry out this kind of order.
+    NotCapable,
+    /// The order itself makes no sense, e.g. a patrol without waypoints.
+    Invalid,
+    /// The supplies involved do not fit into the storages meant to take them.
+    NoRoom,
+}
+
+/// Adds an order to a unit's queue.
+#[derive(Message, Debug)]
+pub struct IssueOrderMessage {
+    pub unit: Entity,
+    pub order: Order,
+    /// Append to the queue instead of cancelling everything the unit is doing.
+    pub queued: bool,
+}
+
+/// Cancels the pending orders of a unit. The active order keeps executing unless `active` is
+/// set.
+#[derive(Message, Debug)]
+pub struct ClearOrdersMessage {
+    pub unit: Entity,
+    pub active: bool,
+}
+
+/// Sent by the systems carrying out an order once it is done.
+#[derive(Message, Debug)]
+pub struct OrderFinishedMessage {
+    pub unit: Entity,
+    pub id: OrderId,
+    pub result: Result<(), OrderFailure>,
+}
+
+/// Sent whenever an order enters a new state.
+#[derive(Message, Debug, Clone)]
+pub struct OrderStateChangedMessage {
+    pub unit: Entity,
+    pub id: OrderId,
+    pub order: Order,
+    pub state: OrderState,
+}
+
+/// Orders a unit executes one after another. The next order becomes active as soon as the
+/// previous one finishes.
+#[derive(Component, Debug, Default, Reflect)]
+pub struct OrderQueue {
+    next_id: u64,
+    active: Option<(OrderId, Order)>,
+    pending: VecDeque<(OrderId, Order)>,
+}
+
+impl OrderQueue {
+    pub fn active(&self) -> Option<(OrderId, &Order)> {
+        self.active.as_ref().map(|(id, order)| (*id, order))
+    }
+
+    /// Orders waiting for the active one to finish, in execution order.
+    pub fn pending(&self) -> impl Iterator<Item = &Order> {
+        self.pending.iter().map(|(_, order)| order)
+    }
+
+    /// Where the unit ends up once every queued order which moves it is carried out.
+    pub fn last_destination(&self) -> Option<HexPosition> {
+        self.pending
+            .iter()
+            .rev()
+            .chain(self.active.iter())
+            .find_map(|(_, order)| order.destination())
+    }
+
+    pub fn is_active(&self, id: OrderId) -> bool {
+        self.active
+            .as_ref()
+            .is_some_and(|(active, _)| *active == id)
+    }
+
+    fn push(&mut self, order: Order) -> OrderId {
+        let id = OrderId(self.next_id);
+        self.next_id += 1;
+        self.pending.push_back((id, order));
+        id
+    }
+}
+
+fn issue_move_order(
+    mut orders: MessageReader<MoveOrderIssuedMessage>,
+    selected_units: Res<SelectedUnitList>,
+    units: Query<(
+        &Unit,
+        &GamePosition,
+        &MovementConfig,
+        Option<&OrderQueue>,
+        Option<&MovementStats>,
+    )>,
+    terrain: Query<&DifficultTerrain>,
+    unit_types: Res<UnitTypeList>,
+    occupancy: Res<HexOccupancy>,
+    grid: Res<HexGrid>,
+    group_config: Res<GroupMoveConfig>,
+    subordinates: Query<&Subordinates>,
+    mut issued: MessageWriter<IssueOrderMessage>,
+) {
+    let default_stats = MovementStats::default();
+    let stats_of = |entity: Entity| {
+        units
+            .get(entity)
+            .ok()
+            .and_then(|(unit, .., own_stats)| own_stats.or_else(|| unit_types.movement_stats(unit)))
+            .unwrap_or(&default_stats)
+    };
+    for order in orders.read() {
+        // Queued orders start where the unit's last queued move ends.
+        let starts: Vec<(Entity, HexPosition)> = selected_units
+            .units()
+            .iter()
+            .flat_map(|&entity| atomic_units(entity, &subordinates))
+            .filter_map(|entity| {
+                let (_, position, _, queue, _) = units.get(entity).ok()?;
+                let queued_destination = queue
+                    .filter(|_| order.queued)
+                    .and_then(OrderQueue::last_destination);
+                Some((entity, queued_destination.unwrap_or(position.hex)))
+            })
+            .collect();
+        // Units are spread over hexes with room left, unless already standing in them.
+        let destinations = group_destinations(&starts, order.destination, |entity, hex| {
+            let has_room = units
+                .get(entity)
+                .is_ok_and(|(_, position, ..)| position.hex == hex)
+                || occupancy.has_room(hex, occupancy.stacking_value_of(entity));
+            has_room
+                && grid
+                    .terrain_at(hex, &terrain)
+                    .is_some_and(|terrain| stats_of(entity).step_cost(&terrain, None).is_some())
+        });
+        let max_speed = (group_config.match_slowest_pace && starts.len() > 1)
+            .then(|| {
+                starts
+                    .iter()
+                    .filter_map(|&(entity, _)| {
+                        let (_, _, config, ..) = units.get(entity).ok()?;
+                        Some(stats_of(entity).speed(config.mode))
+                    })
+                    .reduce(f32::min)
+            })
+            .flatten();
+        for (unit, destination) in destinations {
+            issued.write(IssueOrderMessage {
+                unit,
+                order: Order::Move {
+                    destination,
+                    max_speed,
+                },
+                queued: order.queued,
+            });
+        }
+    }
+}
+
+fn clear_selected_orders(
+    input: Single<&ActionState<GameActions>>,
+    units: Res<SelectedUnitList>,
+    mut clear: MessageWriter<ClearOrdersMessage>,
+) {
+    if !input.just_pressed(&GameActions::ClearOrders) {
+        return;
+    }
+    for &unit in units.units() {
+        clear.write(ClearOrdersMessage {
+            unit,
+            active: false,
+        });
+    }
+}
+
+fn update_order_queues(
+    mut issued: MessageReader<IssueOrderMessage>,
+    mut cleared: MessageReader<ClearOrdersMessage>,
+    mut finished: MessageReader<OrderFinishedMessage>,
+    mut queues: Query<(Entity, &mut OrderQueue)>,
+    subordinates: Query<&Subordinates>,
+    convoy_members: Query<(), With<ConvoyMember>>,
+    mut transitions: MessageWriter<OrderStateChangedMessage>,
+) {
+    let mut transition = |unit: Entity, id: OrderId, order: &Order, state: OrderState| {
+        debug!(target: "orders", "Unit {:?} order {:?} {:?} is now {:?}", unit, id, order, state);
+        transitions.write(OrderStateChangedMessage {
+            unit,
+            id,
+            order: order.clone(),
+            state,
+        });
+    };
+
+    for message in finished.read() {
+        let Ok((_, mut queue)) = queues.get_mut(message.unit) else {
+            continue;
+        };
+        if !queue.is_active(message.id) {
+            continue;
+        }
+        if let Some((id, order)) = queue.active.take() {
+            let state = match &message.result {
+                Ok(()) => OrderState::Completed,
+                Err(failure) => OrderState::Failed(failure.clone()),
+            };
+            transition(message.unit, id, &order, state);
+        }
+    }
+    // Orders given to a composite unit are passed down to all of its atomic units. Vehicles
+    // travelling in a convoy only take orders given to them directly.
+    let convoy_members = &convoy_members;
+    let receivers = |unit: Entity| {
+        atomic_units(unit, &subordinates)
+            .into_iter()
+            .filter(move |&atomic| atomic == unit || !convoy_members.contains(atomic))
+    };
+    for message in cleared.read() {
+        for unit in receivers(message.unit) {
+            let Ok((_, mut queue)) = queues.get_mut(unit) else {
+                continue;
+            };
+            let active = queue.active.take_if(|_| message.active);
+            let cancelled: Vec<_> = active.into_iter().chain(queue.pending.drain(..)).collect();
+            for (id, order) in cancelled {
+                transition(unit, id, &order, OrderState::Cancelled);
+            }
+        }
+    }
+    for message in issued.read() {
+        for unit in receivers(message.unit) {
+            let Ok((_, mut queue)) = queues.get_mut(unit) else {
+                continue;
+            };
+            if !message.queued {
+                let cancelled = queue
+                    .active
+                    .take()
+                    .into_iter()
+                    .chain(queue.pending.drain(..));
+                for (id, order) in cancelled.collect::<Vec<_>>() {
+                    transition(unit, id, &order, OrderState::Cancelled);
+                }
+            }
+            let id = queue.push(message.order.clone());
+            transition(unit, id, &message.order, OrderState::Pending);
+        }
+    }
+
+    for (unit, mut queue) in queues.iter_mut() {
+        if queue.active.is_some() || queue.pending.is_empty() {
+            continue;
+        }
+        if let Some((id, order)) = queue.pending.pop_front() {
+            transition(unit, id, &order, OrderState::Active);
+            queue.active = Some((id, order));
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        entity::Entity,
+        message::{MessageReader, MessageWriter},
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Query, Res},
+    },
+    platform::collections::HashMap,
+};
+
+use hexx::Hex;
+
+use crate::{
+    map::{HexGrid, HexPosition},
+    movement::{
+        DestinationReachedMessage, DifficultTerrain, GamePosition, MoveUnitMessage, MovementStats,
+        MovingTowards, Path, PathNotFoundMessage, SpeedLimit,
+    },
+    time::CurrentTimePoint,
+    unit_managment::orders::{
+        Order, OrderFailure, OrderFinishedMessage, OrderQueue, OrderState,
+        OrderStateChangedMessage, OrderSystems,
+    },
+    units::{HexOccupancy, Unit, UnitTypeList},
+};
+
+/// Carries out move, hold, patrol and escort orders.
+pub struct MovementOrdersPlugin;
+
+impl Plugin for MovementOrdersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (execute_movement_orders, execute_hold_orders).in_set(OrderSystems::Execute),
+        );
+    }
+}
+
+/// Progress of units on a patrol or escort, keyed by unit.
+#[derive(Default)]
+struct MovementOrderProgress {
+    /// Index of the waypoint a patrolling unit is heading to.
+    patrol_waypoint: HashMap<Entity, usize>,
+    /// Hex the target of an escorting unit was in when the unit was last sent after it.
+    escort_target: HashMap<Entity, HexPosition>,
+    /// Hex an escorting unit was last sent to.
+    escort_destination: HashMap<Entity, HexPosition>,
+}
+
+impl MovementOrderProgress {
+    fn forget(&mut self, unit: Entity) {
+        self.patrol_waypoint.remove(&unit);
+        self.escort_target.remove(&unit);
+        self.escort_destination.remove(&unit);
+    }
+}
+
+fn is_moving_order(order: &Order) -> bool {
+    matches!(
+        order,
+        Order::Move { .. } | Order::Patrol { .. } | Order::Escort { .. }
+    )
+}
+
+fn execute_movement_orders(
+    mut transitions: MessageReader<OrderStateChangedMessage>,
+    mut reached: MessageReader<DestinationReachedMessage>,
+    mut not_found: MessageReader<PathNotFoundMessage>,
+    queues: Query<(Entity, &OrderQueue)>,
+    positions: Query<&GamePosition>,
+    units: Query<(&Unit, Option<&MovementStats>)>,
+    terrain: Query<&DifficultTerrain>,
+    unit_types: Res<UnitTypeList>,
+    occupancy: Res<HexOccupancy>,
+    grid: Res<HexGrid>,
+    mut routes: Query<(&MovingTowards, &mut Path)>,
+    mut progress: Local<MovementOrderProgress>,
+    mut moves: MessageWriter<MoveUnitMessage>,
+    mut finished: MessageWriter<OrderFinishedMessage>,
+    mut commands: Commands,
+) {
+    let stats_of = |entity: Entity| {
+        let (unit, own_stats) = units.get(entity).ok()?;
+        own_stats.or_else(|| unit_types.movement_stats(unit))
+    };
+    for message in transitions.read() {
+        let unit = message.unit;
+        match (&message.state, &message.order) {
+            (
+                OrderState::Active,
+                Order::Move {
+                    destination,
+                    max_speed,
+                },
+            ) => {
+                match max_speed {
+                    Some(kph) => commands.entity(unit).insert(SpeedLimit { kph: *kph }),
+                    None => commands.entity(unit).remove::<SpeedLimit>(),
+                };
+                moves.write(MoveUnitMessage {
+                    unit,
+                    destination: *destination,
+                });
+            }
+            (OrderState::Active, Order::Hold { .. }) => stop(unit, &mut routes),
+            // A patrol needs somewhere to go back and forth between.
+            (OrderState::Active, Order::Patrol { waypoints }) if waypoints.len() >= 2 => {
+                progress.patrol_waypoint.insert(unit, 0);
+                moves.write(MoveUnitMessage {
+                    unit,
+                    destination: waypoints[0],
+                });
+            }
+            (OrderState::Active, Order::Patrol { .. }) => {
+                finished.write(OrderFinishedMessage {
+                    unit,
+                    id: message.id,
+                    result: Err(OrderFailure::Invalid),
+                });
+            }
+            // Escorts are sent on their way below, together with escorts whose target moved.
+            (OrderState::Active, Order::Escort { .. }) => progress.forget(unit),
+            (OrderState::Cancelled, order) if is_moving_order(order) => {
+                stop(unit, &mut routes);
+                commands.entity(unit).remove::<SpeedLimit>();
+                progress.forget(unit);
+            }
+            (OrderState::Completed | OrderState::Failed(_), order) if is_moving_order(order) => {
+                commands.entity(unit).remove::<SpeedLimit>();
+                progress.forget(unit);
+            }
+            _ => {}
+        }
+    }
+
+    for message in reached.read() {
+        let Some((id, order)) = queues
+            .get(message.unit)
+            .ok()
+            .and_then(|(_, queue)| queue.active())
+        else {
+            continue;
+        };
+        match order {
+            Order::Move { destination, .. } if *destination == message.destination => {
+                finished.write(OrderFinishedMessage {
+                    unit: message.unit,
+                    id,
+                    result: Ok(()),
+                });
+            }
+            Order::Patrol { waypoints } => {
+                let Some(index) = progress.patrol_waypoint.get_mut(&message.unit) else {
+                    continue;
+                };
+                if waypoints.get(*index) != Some(&message.destination) {
+                    continue;
+                }
+                *index = (*index + 1) % waypoints.len();
+                moves.write(MoveUnitMessage {
+                    unit: message.unit,
+                    destination: waypoints[*index],
+                });
+            }
+            _ => {}
+        }
+    }
+
+    for message in not_found.read() {
+        let Some((id, order)) = queues
+            .get(message.unit)
+            .ok()
+            .and_then(|(_, queue)| queue.active())
+        else {
+            continue;
+        };
+        let sent_there = match order {
+            Order::Move { destination, .. } => *destination == message.destination,
+            Order::Patrol { waypoints } => waypoints.contains(&message.destination),
+            Order::Escort { .. } => {
+                progress.escort_destination.get(&message.unit) == Some(&message.destination)
+            }
+            _ => false,
+        };
+        if sent_there {
+            finished.write(OrderFinishedMessage {
+                unit: message.unit,
+                id,
+                result: Err(OrderFailure::PathNotFound(message.error)),
+            });
+        }
+    }
+
+    for (unit, queue) in queues.iter() {
+        let Some((id, Order::Escort { target })) = queue.active() else {
+            continue;
+        };
+        let Ok(target_position) = positions.get(*target) else {
+            finished.write(OrderFinishedMessage {
+                unit,
+                id,
+                result: Err(OrderFailure::TargetLost),
+            });
+            continue;
+        };
+        if progress.escort_target.get(&unit) == Some(&target_position.hex) {
+            continue;
+        }
+        progress.escort_target.insert(unit, target_position.hex);
+        let Ok(position) = positions.get(unit) else {
+            continue;
+        };
+        let destination = escort_destination(position.hex, target_position.hex, |hex| {
+            occupancy.has_room(hex, occupancy.stacking_value_of(unit))
+                && grid.terrain_at(hex, &terrain).is_some_and(|terrain| {
+                    stats_of(unit).is_some_and(|stats| stats.step_cost(&terrain, None).is_some())
+                })
+        });
+        progress.escort_destination.insert(unit, destination);
+        moves.write(MoveUnitMessage { unit, destination });
+    }
+}
+
+/// Hex next to `target` an escort heading there from `start` should stop in, the closest one
+/// `can_stop` accepts. Escorts already next to their target stay where they are, and fall back
+/// on the target's own hex if there is no room around it.
+fn escort_destination(start: Hex, target: Hex, can_stop: impl Fn(Hex) -> bool) -> Hex {
+    if start.unsigned_distance_to(target) <= 1 {
+        return start;
+    }
+    target
+        .all_neighbors()
+        .into_iter()
+        .filter(|&hex| can_stop(hex))
+        .min_by_key(|&hex| hex.unsigned_distance_to(start))
+        .unwrap_or(target)
+}
+
+/// Cuts the unit's path short so it stops in the hex it is currently moving into.
+fn stop(unit: Entity, routes: &mut Query<(&MovingTowards, &mut Path)>) {
+    if let Ok((moving, mut path)) = routes.get_mut(unit) {
+        path.waypoints = vec![moving.destination];
+    }
+}
+
+fn execute_hold_orders(
+    queues: Query<(Entity, &OrderQueue)>,
+    current_time: Res<CurrentTimePoint>,
+    mut finished: MessageWriter<OrderFinishedMessage>,
+) {
+    for (unit, queue) in queues.iter() {
+        if let Some((id, Order::Hold { until: Some(until) })) = queue.active()
+            && current_time.0 >= *until
+        {
+            finished.write(OrderFinishedMessage {
+                unit,
+                id,
+                result: Ok(()),
+            });
+        }
+    }
+}
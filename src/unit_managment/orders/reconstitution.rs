@@ -0,0 +1,84 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        entity::Entity,
+        message::{MessageReader, MessageWriter},
+        schedule::IntoScheduleConfigs,
+        system::{Local, Query, Res, ResMut},
+    },
+    platform::collections::HashMap,
+};
+
+use crate::{
+    time::{
+        CurrentTimePoint, GameEventScheduler, ScheduledEvent, ScheduledEventId,
+        ScheduledEventMessage,
+    },
+    unit_managment::orders::{
+        Order, OrderFinishedMessage, OrderQueue, OrderState, OrderStateChangedMessage, OrderSystems,
+    },
+};
+
+/// Carries out reconstitute orders. The unit is taken out of action for the order's duration,
+/// tracked by the game event scheduler.
+pub struct ReconstitutionOrdersPlugin;
+
+impl Plugin for ReconstitutionOrdersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            execute_reconstitute_orders.in_set(OrderSystems::Execute),
+        );
+    }
+}
+
+fn execute_reconstitute_orders(
+    mut transitions: MessageReader<OrderStateChangedMessage>,
+    mut events: MessageReader<ScheduledEventMessage>,
+    queues: Query<&OrderQueue>,
+    current_time: Res<CurrentTimePoint>,
+    mut scheduler: ResMut<GameEventScheduler>,
+    mut scheduled: Local<HashMap<Entity, ScheduledEventId>>,
+    mut finished: MessageWriter<OrderFinishedMessage>,
+) {
+    for message in transitions.read() {
+        let Order::Reconstitute { duration } = message.order else {
+            continue;
+        };
+        match message.state {
+            OrderState::Active => {
+                let id = scheduler.schedule_once(
+                    current_time.0 + duration,
+                    ScheduledEvent::ReconstitutionComplete { unit: message.unit },
+                );
+                scheduled.insert(message.unit, id);
+            }
+            OrderState::Cancelled => {
+                if let Some(id) = scheduled.remove(&message.unit) {
+                    scheduler.cancel(id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for message in events.read() {
+        let ScheduledEvent::ReconstitutionComplete { unit } = message.event else {
+            continue;
+        };
+        if scheduled.get(&unit) != Some(&message.id) {
+            continue;
+        }
+        scheduled.remove(&unit);
+        let Some((id, Order::Reconstitute { .. })) =
+            queues.get(unit).ok().and_then(OrderQueue::active)
+        else {
+            continue;
+        };
+        finished.write(OrderFinishedMessage {
+            unit,
+            id,
+            result: Ok(()),
+        });
+    }
+}
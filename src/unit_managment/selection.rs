@@ -1,7 +1,136 @@
-use bevy::app::{App, Plugin};
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        entity::Entity,
+        lifecycle::RemovedComponents,
+        message::{MessageReader, MessageWriter},
+        query::Added,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Query, Res, ResMut, Single},
+    },
+    reflect::Reflect,
+};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    game_actions::GameActions,
+    unit_managment::{SelectUnitMessage, SelectedUnitList, SelectionMode},
+    units::{ConvoyMember, Unit},
+};
 
 pub struct SelectionPlugin;
 
 impl Plugin for SelectionPlugin {
-    fn build(&self, app: &mut App) {}
+    fn build(&self, app: &mut App) {
+        app.register_type::<SelectionGroups>()
+            .init_resource::<SelectionGroups>()
+            .add_systems(
+                Update,
+                (
+                    prune_despawned_units,
+                    handle_selection_groups,
+                    apply_selection_messages,
+                )
+                    .chain(),
+            );
+    }
+}
+
+pub const SELECTION_GROUP_COUNT: usize = 9;
+
+/// Selections saved with Ctrl+1..9 and recalled with 1..9.
+#[derive(Resource, Reflect, Default, Debug)]
+pub struct SelectionGroups {
+    groups: [Vec<Entity>; SELECTION_GROUP_COUNT],
+}
+
+impl SelectionGroups {
+    pub fn get(&self, index: usize) -> &[Entity] {
+        self.groups.get(index).map_or(&[], Vec::as_slice)
+    }
+}
+
+const GROUP_ACTIONS: [GameActions; SELECTION_GROUP_COUNT] = [
+    GameActions::SelectionGroup1,
+    GameActions::SelectionGroup2,
+    GameActions::SelectionGroup3,
+    GameActions::SelectionGroup4,
+    GameActions::SelectionGroup5,
+    GameActions::SelectionGroup6,
+    GameActions::SelectionGroup7,
+    GameActions::SelectionGroup8,
+    GameActions::SelectionGroup9,
+];
+
+fn apply_selection_messages(
+    mut selected_units: ResMut<SelectedUnitList>,
+    mut messages: MessageReader<SelectUnitMessage>,
+) {
+    for message in messages.read() {
+        let selected = &mut selected_units.selected_units;
+        match message.mode {
+            SelectionMode::Replace | SelectionMode::Add => {
+                if message.mode == SelectionMode::Replace {
+                    selected.clear();
+                }
+                for &unit in &message.units {
+                    if !selected.contains(&unit) {
+                        selected.push(unit);
+                    }
+                }
+            }
+            SelectionMode::Toggle => {
+                for &unit in &message.units {
+                    match selected.iter().position(|&s| s == unit) {
+                        Some(index) => {
+                            selected.remove(index);
+                        }
+                        None => selected.push(unit),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn handle_selection_groups(
+    input: Single<&ActionState<GameActions>>,
+    selected_units: Res<SelectedUnitList>,
+    mut groups: ResMut<SelectionGroups>,
+    mut writer: MessageWriter<SelectUnitMessage>,
+) {
+    let saving = input.pressed(&GameActions::ToggleSelection);
+    for (index, action) in GROUP_ACTIONS.iter().enumerate() {
+        if !input.just_pressed(action) {
+            continue;
+        }
+        if saving {
+            groups.groups[index] = selected_units.selected_units.clone();
+        } else {
+            writer.write(SelectUnitMessage {
+                units: groups.get(index).to_vec(),
+                mode: SelectionMode::Replace,
+            });
+        }
+    }
+}
+
+/// Drops units which are gone, or which joined a convoy and are no longer on the map.
+fn prune_despawned_units(
+    mut removed: RemovedComponents<Unit>,
+    joined_convoy: Query<Entity, Added<ConvoyMember>>,
+    mut selected_units: ResMut<SelectedUnitList>,
+    mut groups: ResMut<SelectionGroups>,
+) {
+    let removed: Vec<Entity> = removed.read().chain(joined_convoy.iter()).collect();
+    if removed.is_empty() {
+        return;
+    }
+    selected_units
+        .selected_units
+        .retain(|unit| !removed.contains(unit));
+    for group in groups.groups.iter_mut() {
+        group.retain(|unit| !removed.contains(unit));
+    }
 }
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{App, FixedUpdate, Plugin},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::Without,
+        reflect::ReflectComponent,
+        system::{Commands, Local, Query, Res},
+    },
+    log::debug,
+    reflect::Reflect,
+};
+use serde::Deserialize;
+
+use crate::{
+    movement::{DistanceTravelled, MovemenetPenalty, MovementPenalties, MovementPenaltyReason},
+    resources::{ResourceAmout, ResourceKey, ResourceStack, ResourceTypeId, ResourceTypes},
+    time::{CurrentTimePoint, GameTime},
+    units::{ConvoyMember, SupplyStorage, Unit, UnitTypeList},
+};
+
+/// Movement penalty applied to units which could not draw all the supplies they need.
+pub const SHORTAGE_MOVEMENT_PENALTY: f32 = 2.0;
+
+pub struct ConsumptionPlugin;
+
+impl Plugin for ConsumptionPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SupplyConsumption>()
+            .register_type::<ConsumptionRate>()
+            .register_type::<SupplyShortage>()
+            .register_type::<InCombat>()
+            .add_systems(FixedUpdate, consume_supplies);
+    }
+}
+
+/// Amount of a resource used up per unit of time or distance. Consumption always draws from
+/// fluid stock, palletized supplies have to be transloaded first.
+#[derive(Debug, Clone, Reflect, Deserialize)]
+pub struct ConsumptionRate {
+    pub resource: ResourceKey,
+    pub amount: f32,
+}
+
+/// Consumption of a unit type. When attached to an entity, as with convoys, it takes
+/// precedence over the consumption of its unit type.
+#[derive(Debug, Clone, Default, Reflect, Deserialize, Component)]
+#[reflect(Component)]
+pub struct SupplyConsumption {
+    #[serde(default)]
+    pub per_day: Vec<ConsumptionRate>,
+    #[serde(default)]
+    pub per_km: Vec<ConsumptionRate>,
+    /// Consumed only while the unit is [`InCombat`].
+    #[serde(default)]
+    pub per_combat_hour: Vec<ConsumptionRate>,
+}
+
+impl SupplyConsumption {
+    pub fn rates(&self) -> impl Iterator<Item = &ConsumptionRate> {
+        self.per_day
+            .iter()
+            .chain(self.per_km.iter())
+            .chain(self.per_combat_hour.iter())
+    }
+}
+
+/// Marks a unit engaged in combat.
+#[derive(Component, Debug, Reflect)]
+pub struct InCombat;
+
+/// Resources a unit consumes but has run out of. Units with a shortage are also slowed down.
+#[derive(Component, Debug, Reflect)]
+pub struct SupplyShortage {
+    pub missing: Vec<ResourceTypeId>,
+}
+
+fn consume_supplies(
+    mut units: Query<
+        (
+            Entity,
+            &Unit,
+            &mut SupplyStorage,
+            Option<&mut DistanceTravelled>,
+            Option<&mut MovementPenalties>,
+            Option<&InCombat>,
+            Option<&SupplyConsumption>,
+            Option<&SupplyShortage>,
+        ),
+        Without<ConvoyMember>,
+    >,
+    unit_types: Res<UnitTypeList>,
+    resource_types: Res<ResourceTypes>,
+    current_time: Res<CurrentTimePoint>,
+    mut last_time: Local<Option<GameTime>>,
+    mut commands: Commands,
+) {
+    let now = current_time.0;
+    let elapsed = last_time.map_or(Duration::ZERO, |last| now - last);
+    *last_time = Some(now);
+    let elapsed_hours = elapsed.as_secs_f32() / 3600.0;
+
+    for (entity, unit, mut storage, distance, penalties, in_combat, own_consumption, shortage) in
+        units.iter_mut()
+    {
+        let kilometers =
+            distance.map_or(0.0, |mut distance| std::mem::take(&mut distance.kilometers));
+        let Some(consumption) = own_consumption.or_else(|| {
+            unit_types
+                .types
+                .get(&unit.unit_type)
+                .map(|details| &details.consumption)
+        }) else {
+            continue;
+        };
+        let combat_hours = if in_combat.is_some() {
+            elapsed_hours
+        } else {
+            0.0
+        };
+        let demands = consumption
+            .per_day
+            .iter()
+            .map(|rate| (rate, elapsed_hours / 24.0))
+            .chain(consumption.per_km.iter().map(|rate| (rate, kilometers)))
+            .chain(
+                consumption
+                    .per_combat_hour
+                    .iter()
+                    .map(|rate| (rate, combat_hours)),
+            );
+
+        for (rate, factor) in demands {
+            let amount = rate.amount * factor;
+            let Ok(resource_type) = resource_types.resolve(&rate.resource) else {
+                continue;
+            };
+            if amount <= 0.0 {
+                continue;
+            }
+            storage.remove(ResourceStack {
+                resource_type,
+                amount: ResourceAmout::Fluid { count: amount },
+            });
+        }
+
+        // Judged by the stock left rather than by this step's demand, so a unit out of fuel
+        // stays short of it while standing still.
+        let fluid = ResourceAmout::Fluid { count: 0.0 };
+        let mut missing = Vec::new();
+        for rate in consumption.rates() {
+            let Ok(resource_type) = resource_types.resolve(&rate.resource) else {
+                continue;
+            };
+            if storage
+                .get(resource_type, &fluid)
+                .is_none_or(|stack| stack.amount.is_empty())
+                && !missing.contains(&resource_type)
+            {
+                missing.push(resource_type);
+            }
+        }
+
+        // Only touched when the shortage changes, so systems watching for changes to either
+        // are not woken up every step.
+        if missing.is_empty() {
+            if shortage.is_some() {
+                commands.entity(entity).remove::<SupplyShortage>();
+                if let Some(mut penalties) = penalties {
+                    penalties.remove(MovementPenaltyReason::SupplyShortage);
+                }
+            }
+        } else if shortage.is_none_or(|shortage| shortage.missing != missing) {
+            debug!("Unit {entity:?} is short of {missing:?}");
+            if shortage.is_none()
+                && let Some(mut penalties) = penalties
+            {
+                penalties.set(MovemenetPenalty {
+                    value: SHORTAGE_MOVEMENT_PENALTY,
+                    reason: MovementPenaltyReason::SupplyShortage,
+                });
+            }
+            commands.entity(entity).insert(SupplyShortage { missing });
+        }
+    }
+}
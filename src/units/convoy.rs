@@ -0,0 +1,150 @@
+use bevy::{
+    app::{App, Plugin},
+    color::{Color, LinearRgba},
+    ecs::{bundle::Bundle, component::Component, entity::Entity, name::Name},
+    math::Vec2,
+    reflect::Reflect,
+    sprite::Sprite,
+    transform::components::Transform,
+};
+
+use crate::{
+    map::HexPosition,
+    movement::{
+        DistanceTravelled, GamePosition, MovementConfig, MovementMode, MovementPenalties,
+        MovementStats,
+    },
+    unit_managment::orders::OrderQueue,
+    units::{Echelon, SupplyConsumption, SupplyStorage, SupplyStorageLimits, Unit},
+};
+
+/// Unit type of convoy entities. It has no definition, convoys carry their own movement stats
+/// and consumption derived from their members.
+pub const CONVOY_UNIT_TYPE: &str = "Convoy";
+pub const MIN_CONVOY_SIZE: usize = 2;
+
+pub struct ConvoyPlugin;
+
+impl Plugin for ConvoyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Convoy>()
+            .register_type::<ConvoyMember>();
+    }
+}
+
+/// Vehicles moving together as a single unit. While in the convoy, members are taken off the
+/// map and their supplies are held in the convoy's [`SupplyStorage`].
+#[derive(Component, Debug, Reflect)]
+pub struct Convoy {
+    pub members: Vec<Entity>,
+}
+
+#[derive(Component, Debug, Reflect)]
+pub struct ConvoyMember {
+    pub convoy: Entity,
+}
+
+#[derive(Bundle)]
+pub struct ConvoyBundle {
+    unit: Unit,
+    convoy: Convoy,
+    position: GamePosition,
+    sprite: Sprite,
+    transform: Transform,
+    config: MovementConfig,
+    stats: MovementStats,
+    consumption: SupplyConsumption,
+    manifest: SupplyStorage,
+    penalties: MovementPenalties,
+    distance: DistanceTravelled,
+    orders: OrderQueue,
+    name: Name,
+}
+
+impl ConvoyBundle {
+    pub fn new(
+        position: HexPosition,
+        members: Vec<Entity>,
+        stats: MovementStats,
+        consumption: SupplyConsumption,
+        manifest: SupplyStorage,
+    ) -> Self {
+        Self {
+            name: Name::new(format!("Convoy ({} vehicles)", members.len())),
+            unit: Unit {
+                unit_type: CONVOY_UNIT_TYPE.to_string(),
+                echelon: Echelon::Platoon,
+            },
+            convoy: Convoy { members },
+            position: GamePosition { hex: position },
+            transform: Transform::from_xyz(0.0, 0.0, 1.0),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(24.0, 12.0)),
+                color: Color::LinearRgba(LinearRgba::rgb(1.0, 0.8, 0.1)),
+                ..Default::default()
+            },
+            config: MovementConfig {
+                mode: MovementMode::Strategic,
+            },
+            stats,
+            consumption,
+            manifest,
+            penalties: MovementPenalties::default(),
+            distance: DistanceTravelled::default(),
+            orders: OrderQueue::default(),
+        }
+    }
+}
+
+/// Stats of a convoy: the speed of its slowest member, the worst penalty of any member for
+/// each terrain, and every terrain any member cannot enter.
+pub fn combined_movement_stats<'a>(
+    members: impl IntoIterator<Item = &'a MovementStats>,
+) -> MovementStats {
+    let mut members = members.into_iter();
+    let Some(first) = members.next() else {
+        return MovementStats::default();
+    };
+    members.fold(first.clone(), |mut combined, stats| {
+        combined.tactical_speed = combined.tactical_speed.min(stats.tactical_speed);
+        combined.strategic_speed = combined.strategic_speed.min(stats.strategic_speed);
+        for (terrain, penalty) in stats.difficult_terrain_penalty.iter() {
+            let worst = combined.get_penalty(terrain).max(*penalty);
+            combined.difficult_terrain_penalty.insert(*terrain, worst);
+        }
+        combined
+            .impassable_terrain
+            .extend(stats.impassable_terrain.iter().copied());
+        combined
+    })
+}
+
+/// Everything the members of a convoy consume, drawn from the convoy's manifest.
+pub fn combined_consumption<'a>(
+    members: impl IntoIterator<Item = &'a SupplyConsumption>,
+) -> SupplyConsumption {
+    members
+        .into_iter()
+        .fold(SupplyConsumption::default(), |mut combined, consumption| {
+            combined.per_day.extend(consumption.per_day.iter().cloned());
+            combined.per_km.extend(consumption.per_km.iter().cloned());
+            combined
+                .per_combat_hour
+                .extend(consumption.per_combat_hour.iter().cloned());
+            combined
+        })
+}
+
+/// Empty manifest able to hold what all members can carry. A limit is only kept if every
+/// member has one.
+pub fn combined_manifest(members: impl IntoIterator<Item = SupplyStorageLimits>) -> SupplyStorage {
+    let sum = |a: Option<f32>, b: Option<f32>| Some(a? + b?);
+    let limits = members
+        .into_iter()
+        .reduce(|combined, limits| SupplyStorageLimits {
+            max_weight: sum(combined.max_weight, limits.max_weight),
+            max_volume: sum(combined.max_volume, limits.max_volume),
+        })
+        .unwrap_or_default();
+    SupplyStorage::new(limits.max_weight, limits.max_volume)
+}
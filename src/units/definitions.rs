@@ -0,0 +1,257 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::{
+        Asset, AssetApp, AssetEvent, AssetLoader, AssetServer, Assets, Handle, LoadContext,
+        io::Reader,
+    },
+    ecs::{
+        entity::Entity,
+        message::MessageReader,
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+    },
+    log::{error, info},
+    prelude::*,
+    reflect::TypePath,
+    sprite::Sprite,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::{
+    resources::ResourceTypes,
+    units::{SupplyStorage, Unit, UnitDetails, UnitTypeId, UnitTypeList},
+};
+
+pub const UNIT_TYPES_PATH: &str = "units/unit_types.units.ron";
+
+pub struct UnitDefinitionsPlugin;
+
+impl Plugin for UnitDefinitionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<UnitTypeDefinitions>()
+            .init_asset_loader::<UnitTypeDefinitionsLoader>()
+            .add_systems(Startup, load_unit_types)
+            .add_systems(Update, (update_unit_type_list, apply_unit_details).chain());
+    }
+}
+
+/// Contents of a `.units.ron` file.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct UnitTypeDefinitions {
+    pub types: HashMap<UnitTypeId, UnitDetails>,
+}
+
+#[derive(Resource)]
+struct UnitTypeDefinitionsHandle(Handle<UnitTypeDefinitions>);
+
+#[derive(Debug)]
+pub enum UnitDefinitionError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid {
+        unit_type: UnitTypeId,
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for UnitDefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnitDefinitionError::Io(error) => write!(f, "could not read unit types: {error}"),
+            UnitDefinitionError::Parse(error) => write!(f, "could not parse unit types: {error}"),
+            UnitDefinitionError::Invalid { unit_type, reason } => {
+                write!(f, "unit type {unit_type:?} is invalid: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UnitDefinitionError {}
+
+#[derive(Default)]
+struct UnitTypeDefinitionsLoader;
+
+impl AssetLoader for UnitTypeDefinitionsLoader {
+    type Asset = UnitTypeDefinitions;
+    type Settings = ();
+    type Error = UnitDefinitionError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(UnitDefinitionError::Io)?;
+        let mut definitions: UnitTypeDefinitions =
+            ron::de::from_bytes(&bytes).map_err(UnitDefinitionError::Parse)?;
+        for (unit_type, details) in definitions.types.iter_mut() {
+            validate(details).map_err(|reason| UnitDefinitionError::Invalid {
+                unit_type: unit_type.clone(),
+                reason,
+            })?;
+            details.icon = details
+                .icon_path
+                .as_ref()
+                .map(|path| load_context.load(path.clone()));
+        }
+        Ok(definitions)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["units.ron"]
+    }
+}
+
+fn validate(details: &UnitDetails) -> Result<(), String> {
+    let stats = &details.movement_stats;
+    for (name, speed) in [
+        ("tactical_speed", stats.tactical_speed),
+        ("strategic_speed", stats.strategic_speed),
+    ] {
+        if !speed.is_finite() || speed < 0.0 {
+            return Err(format!("{name} must be a non-negative number, got {speed}"));
+        }
+    }
+    if let Some((terrain, penalty)) = stats
+        .difficult_terrain_penalty
+        .iter()
+        .find(|(_, penalty)| !penalty.is_finite() || **penalty <= 0.0)
+    {
+        return Err(format!(
+            "penalty for {terrain:?} must be a positive number, got {penalty}"
+        ));
+    }
+    if !details.stacking_value.is_finite() || details.stacking_value < 0.0 {
+        return Err(format!(
+            "stacking_value must be a non-negative number, got {}",
+            details.stacking_value
+        ));
+    }
+    if let Some(rate) = details.transloading_rate
+        && (!rate.is_finite() || rate <= 0.0)
+    {
+        return Err(format!(
+            "transloading_rate must be a positive number, got {rate}"
+        ));
+    }
+    if let Some(rate) = details
+        .consumption
+        .rates()
+        .find(|rate| !rate.amount.is_finite() || rate.amount < 0.0)
+    {
+        return Err(format!(
+            "consumption of {:?} must be a non-negative number, got {}",
+            rate.resource, rate.amount
+        ));
+    }
+    let limits = &details.supply_storage;
+    for (name, limit) in [
+        ("max_weight", limits.max_weight),
+        ("max_volume", limits.max_volume),
+    ] {
+        if let Some(limit) = limit
+            && (!limit.is_finite() || limit < 0.0)
+        {
+            return Err(format!("{name} must be a non-negative number, got {limit}"));
+        }
+    }
+    Ok(())
+}
+
+fn load_unit_types(mut commands: Commands, server: Res<AssetServer>) {
+    commands.insert_resource(UnitTypeDefinitionsHandle(server.load(UNIT_TYPES_PATH)));
+}
+
+/// Replaces the contents of [`UnitTypeList`] whenever the definitions file is (re)loaded.
+/// Unit types reference resource types by key, so the list is only built once the resource
+/// catalog is available and rebuilt whenever it changes. Unit types referencing unknown
+/// resources are rejected.
+fn update_unit_type_list(
+    mut events: MessageReader<AssetEvent<UnitTypeDefinitions>>,
+    handle: Res<UnitTypeDefinitionsHandle>,
+    definitions: Res<Assets<UnitTypeDefinitions>>,
+    resource_types: Res<ResourceTypes>,
+    mut unit_types: ResMut<UnitTypeList>,
+) {
+    let reloaded = events
+        .read()
+        .filter(|event| {
+            event.is_loaded_with_dependencies(&handle.0) || event.is_modified(&handle.0)
+        })
+        .count()
+        > 0;
+    if !reloaded && !resource_types.is_changed() {
+        return;
+    }
+    if resource_types.types.is_empty() {
+        return;
+    }
+    let Some(definitions) = definitions.get(&handle.0) else {
+        return;
+    };
+    unit_types.types = definitions
+        .types
+        .iter()
+        .filter(|(id, details)| {
+            match details
+                .referenced_resources()
+                .try_for_each(|key| resource_types.resolve(key).map(|_| ()))
+            {
+                Ok(()) => true,
+                Err(error) => {
+                    error!("Unit type {id:?} is invalid: {error}");
+                    false
+                }
+            }
+        })
+        .map(|(id, details)| (id.clone(), details.clone()))
+        .collect();
+    info!("Loaded {} unit types", unit_types.types.len());
+}
+
+/// Keeps spawned units in line with the definition of their type.
+fn apply_unit_details(
+    unit_types: Res<UnitTypeList>,
+    resource_types: Res<ResourceTypes>,
+    mut units: Query<(Entity, &mut Unit, &mut Sprite, Option<&mut SupplyStorage>)>,
+    mut commands: Commands,
+) {
+    let all_units = unit_types.is_changed();
+    for (entity, mut unit, mut sprite, storage) in units.iter_mut() {
+        if !all_units && !unit.is_added() {
+            continue;
+        }
+        let Some(details) = unit_types.types.get(&unit.unit_type) else {
+            continue;
+        };
+        unit.echelon = details.echelon;
+        if let Some(icon) = &details.icon {
+            sprite.image = icon.clone();
+            sprite.color = Color::WHITE;
+        }
+        let limits = details.supply_storage;
+        match storage {
+            Some(mut storage) => {
+                storage.max_weight = limits.max_weight;
+                storage.max_volume = limits.max_volume;
+            }
+            None => {
+                commands.entity(entity).insert(SupplyStorage {
+                    storage: details
+                        .starting_supplies
+                        .iter()
+                        .filter_map(|supply| supply.resolve(&resource_types).ok())
+                        .collect(),
+                    max_weight: limits.max_weight,
+                    max_volume: limits.max_volume,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        bundle::Bundle,
+        component::Component,
+        entity::Entity,
+        message::{Message, MessageReader},
+        name::Name,
+        query::{With, Without},
+        system::{Commands, Query},
+    },
+    log::warn,
+    reflect::Reflect,
+};
+
+use crate::{
+    resources::ResourceStack,
+    units::{Echelon, SupplyStorage, Unit},
+};
+
+/// Unit type of composite units. They have no definition of their own, everything they can
+/// do comes from their subordinates.
+pub const COMPOSITE_UNIT_TYPE: &str = "Headquarters";
+
+pub struct HierarchyPlugin;
+
+impl Plugin for HierarchyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AttachedTo>()
+            .register_type::<Subordinates>()
+            .register_type::<CompositeUnit>()
+            .register_type::<FormationSummary>()
+            .add_message::<AttachUnitMessage>()
+            .add_message::<DetachUnitMessage>()
+            .add_systems(Update, (change_attachments, summarize_formations));
+    }
+}
+
+/// Places a unit under the command of a composite unit.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[relationship(relationship_target = Subordinates)]
+pub struct AttachedTo(pub Entity);
+
+/// Units directly under the command of a composite unit. Kept in sync with [`AttachedTo`].
+#[derive(Component, Debug, Default, Reflect)]
+#[relationship_target(relationship = AttachedTo)]
+pub struct Subordinates(Vec<Entity>);
+
+impl Subordinates {
+    pub fn units(&self) -> &[Entity] {
+        &self.0
+    }
+}
+
+/// A platoon, company or larger formation. Composite units are not on the map themselves,
+/// orders given to them are passed down to their atomic units.
+#[derive(Component, Debug, Default, Reflect)]
+pub struct CompositeUnit;
+
+/// Totals over every atomic unit under a composite unit.
+#[derive(Component, Debug, Default, Reflect)]
+pub struct FormationSummary {
+    /// Number of atomic units.
+    pub strength: u32,
+    pub supplies: Vec<ResourceStack>,
+}
+
+#[derive(Bundle)]
+pub struct CompositeUnitBundle {
+    unit: Unit,
+    composite: CompositeUnit,
+    summary: FormationSummary,
+    name: Name,
+}
+
+impl CompositeUnitBundle {
+    pub fn new(name: impl Into<String>, echelon: Echelon) -> Self {
+        Self {
+            unit: Unit {
+                unit_type: COMPOSITE_UNIT_TYPE.to_string(),
+                echelon,
+            },
+            composite: CompositeUnit,
+            summary: FormationSummary::default(),
+            name: Name::new(name.into()),
+        }
+    }
+}
+
+/// Attaches a unit to a composite unit of a higher echelon, detaching it from its current
+/// superior.
+#[derive(Message, Debug)]
+pub struct AttachUnitMessage {
+    pub unit: Entity,
+    pub superior: Entity,
+}
+
+/// Detaches a unit from its superior, leaving it independent.
+#[derive(Message, Debug)]
+pub struct DetachUnitMessage {
+    pub unit: Entity,
+}
+
+/// Whether a unit of echelon `unit` may be attached to a superior of echelon `superior`.
+/// Echelons strictly grow upwards, which also keeps the hierarchy free of cycles.
+pub fn can_attach(unit: Echelon, superior: Echelon) -> bool {
+    superior > unit
+}
+
+/// Atomic units under `unit`, or `unit` itself if it commands no one.
+pub fn atomic_units(unit: Entity, subordinates: &Query<&Subordinates>) -> Vec<Entity> {
+    let mut atomic = Vec::new();
+    let mut stack = vec![unit];
+    while let Some(unit) = stack.pop() {
+        match subordinates.get(unit) {
+            Ok(children) => stack.extend(children.units().iter().rev()),
+            Err(_) => atomic.push(unit),
+        }
+    }
+    atomic
+}
+
+fn change_attachments(
+    mut attach: MessageReader<AttachUnitMessage>,
+    mut detach: MessageReader<DetachUnitMessage>,
+    units: Query<&Unit>,
+    composites: Query<(), With<CompositeUnit>>,
+    mut commands: Commands,
+) {
+    for message in detach.read() {
+        commands.entity(message.unit).remove::<AttachedTo>();
+    }
+    for message in attach.read() {
+        let (Ok(unit), Ok(superior)) = (units.get(message.unit), units.get(message.superior))
+        else {
+            continue;
+        };
+        if !composites.contains(message.superior) || !can_attach(unit.echelon, superior.echelon) {
+            warn!(
+                "Cannot attach {:?} ({:?}) to {:?} ({:?})",
+                message.unit, unit.echelon, message.superior, superior.echelon
+            );
+            continue;
+        }
+        commands
+            .entity(message.unit)
+            .insert(AttachedTo(message.superior));
+    }
+}
+
+fn summarize_formations(
+    mut composites: Query<(Entity, &mut FormationSummary), With<CompositeUnit>>,
+    subordinates: Query<&Subordinates>,
+    storages: Query<&SupplyStorage, Without<CompositeUnit>>,
+) {
+    for (entity, mut summary) in composites.iter_mut() {
+        let atomic: Vec<Entity> = atomic_units(entity, &subordinates)
+            .into_iter()
+            .filter(|&unit| unit != entity)
+            .collect();
+        let mut supplies = SupplyStorage::new(None, None);
+        for storage in storages.iter_many(&atomic) {
+            for stack in storage.storage.iter() {
+                supplies.insert(*stack);
+            }
+        }
+        let strength = atomic.len() as u32;
+        if summary.strength != strength || summary.supplies != supplies.storage {
+            summary.strength = strength;
+            summary.supplies = supplies.storage;
+        }
+    }
+}
@@ -1,18 +1,53 @@
+mod consumption;
+mod convoy;
+mod definitions;
+mod hierarchy;
+mod spatial_index;
+mod stacking;
 mod supply;
+mod transloading;
 use bevy::{
     app::{App, Plugin},
+    asset::Handle,
     color::LinearRgba,
     ecs::{bundle::Bundle, component::Component, name::Name, resource::Resource},
+    image::Image,
     platform::collections::HashMap,
     reflect::Reflect,
     sprite::{Anchor, Sprite},
     transform::components::Transform,
 };
+use serde::Deserialize;
 
 use crate::{
     map::HexPosition,
-    movement::{GamePosition, MovementConfig, MovementMode, MovementStats},
-    units::supply::SupplyPlugin,
+    movement::{
+        DistanceTravelled, GamePosition, MovementConfig, MovementMode, MovementPenalties,
+        MovementStats,
+    },
+    resources::{ResourceKey, ResourceStackDefinition, VolumeInLitters, WeightInKilograms},
+    unit_managment::orders::OrderQueue,
+    units::{
+        consumption::ConsumptionPlugin, convoy::ConvoyPlugin, definitions::UnitDefinitionsPlugin,
+        hierarchy::HierarchyPlugin, spatial_index::SpatialIndexPlugin, stacking::StackingPlugin,
+        supply::SupplyPlugin, transloading::TransloadingPlugin,
+    },
+};
+
+pub use consumption::{ConsumptionRate, InCombat, SupplyConsumption, SupplyShortage};
+pub use convoy::{
+    CONVOY_UNIT_TYPE, Convoy, ConvoyBundle, ConvoyMember, MIN_CONVOY_SIZE, combined_consumption,
+    combined_manifest, combined_movement_stats,
+};
+pub use hierarchy::{
+    AttachUnitMessage, AttachedTo, COMPOSITE_UNIT_TYPE, CompositeUnit, CompositeUnitBundle,
+    DetachUnitMessage, FormationSummary, Subordinates, atomic_units, can_attach,
+};
+pub use spatial_index::{UnitSpatialIndex, update_unit_spatial_index};
+pub use stacking::{HexOccupancy, Overstacked, StackingConfig};
+pub use supply::{SupplyError, SupplyStorage};
+pub use transloading::{
+    Transloading, TransloadingCompletedMessage, TransloadingError, TransloadingProgressMessage,
 };
 
 pub struct UnitPlugin;
@@ -20,17 +55,66 @@ impl Plugin for UnitPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Unit>()
             .register_type::<Echelon>()
+            .register_type::<Faction>()
             .register_type::<UnitDetails>()
             .register_type::<UnitTypeList>()
-            .add_plugins(SupplyPlugin);
+            .init_resource::<UnitTypeList>()
+            .add_plugins((
+                SupplyPlugin,
+                UnitDefinitionsPlugin,
+                TransloadingPlugin,
+                ConsumptionPlugin,
+                SpatialIndexPlugin,
+                ConvoyPlugin,
+                HierarchyPlugin,
+                StackingPlugin,
+            ));
     }
 }
 
 pub type UnitTypeId = String;
 
-#[derive(Reflect, Default, Debug)]
+#[derive(Reflect, Default, Debug, Clone, Deserialize)]
 pub struct UnitDetails {
+    pub echelon: Echelon,
+    /// Share of a hex's stacking limit taken up by a unit of this type.
+    pub stacking_value: f32,
     pub movement_stats: MovementStats,
+    /// Only vehicles can be formed into convoys.
+    #[serde(default)]
+    pub vehicle: bool,
+    #[serde(default)]
+    pub supply_storage: SupplyStorageLimits,
+    /// Supplies a newly spawned unit of this type carries.
+    #[serde(default)]
+    pub starting_supplies: Vec<ResourceStackDefinition>,
+    /// Pallets per hour a unit of this type breaks down when assigned to a storage. Units
+    /// without a rate cannot transload.
+    #[serde(default)]
+    pub transloading_rate: Option<f32>,
+    #[serde(default)]
+    pub consumption: SupplyConsumption,
+    /// Path of the icon, relative to the assets folder.
+    #[serde(default)]
+    pub icon_path: Option<String>,
+    #[serde(skip)]
+    pub icon: Option<Handle<Image>>,
+}
+
+impl UnitDetails {
+    /// Keys of all resource types this unit type refers to.
+    pub fn referenced_resources(&self) -> impl Iterator<Item = &ResourceKey> {
+        self.starting_supplies
+            .iter()
+            .map(|supply| &supply.resource)
+            .chain(self.consumption.rates().map(|rate| &rate.resource))
+    }
+}
+
+#[derive(Reflect, Default, Debug, Clone, Copy, Deserialize)]
+pub struct SupplyStorageLimits {
+    pub max_weight: Option<WeightInKilograms>,
+    pub max_volume: Option<VolumeInLitters>,
 }
 
 #[derive(Resource, Reflect, Default)]
@@ -38,14 +122,26 @@ pub struct UnitTypeList {
     pub types: HashMap<UnitTypeId, UnitDetails>,
 }
 
+impl UnitTypeList {
+    pub fn movement_stats(&self, unit: &Unit) -> Option<&MovementStats> {
+        self.types
+            .get(&unit.unit_type)
+            .map(|details| &details.movement_stats)
+    }
+}
+
 #[derive(Component, Reflect, Debug)]
 pub struct Unit {
     pub unit_type: UnitTypeId,
     pub echelon: Echelon,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+/// Size of a unit, ordered from the smallest.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect, Default, Deserialize,
+)]
 pub enum Echelon {
+    #[default]
     Squad,
     Platoon,
     Company,
@@ -53,6 +149,10 @@ pub enum Echelon {
     Brigade,
 }
 
+/// Side a unit or depot belongs to, by the faction's name in the map.
+#[derive(Component, Reflect, Debug, Clone, PartialEq, Eq)]
+pub struct Faction(pub String);
+
 #[derive(Component)]
 pub struct AtomicUnit;
 
@@ -64,6 +164,9 @@ pub struct AtomicUnitBundle {
     sprite: Sprite,
     transform: Transform,
     config: MovementConfig,
+    penalties: MovementPenalties,
+    distance: DistanceTravelled,
+    orders: OrderQueue,
     name: Name,
 }
 
@@ -86,6 +189,9 @@ impl AtomicUnitBundle {
             config: MovementConfig {
                 mode: MovementMode::Strategic,
             },
+            penalties: MovementPenalties::default(),
+            distance: DistanceTravelled::default(),
+            orders: OrderQueue::default(),
         }
     }
 }
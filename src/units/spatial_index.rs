@@ -0,0 +1,87 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        entity::Entity,
+        lifecycle::RemovedComponents,
+        query::{Changed, With},
+        resource::Resource,
+        system::{Query, ResMut},
+    },
+    platform::collections::HashMap,
+};
+use hexx::Hex;
+
+use crate::{movement::GamePosition, units::Unit};
+
+pub struct SpatialIndexPlugin;
+
+impl Plugin for SpatialIndexPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UnitSpatialIndex>()
+            .add_systems(Update, update_unit_spatial_index);
+    }
+}
+
+/// Units grouped by the hex they occupy. Kept up to date by [`update_unit_spatial_index`],
+/// systems reading it in `Update` should run after it.
+#[derive(Resource, Debug, Default)]
+pub struct UnitSpatialIndex {
+    by_hex: HashMap<Hex, Vec<Entity>>,
+    positions: HashMap<Entity, Hex>,
+}
+
+impl UnitSpatialIndex {
+    /// Units in the hex, in the order they entered it.
+    pub fn units_at(&self, hex: Hex) -> &[Entity] {
+        self.by_hex.get(&hex).map_or(&[], Vec::as_slice)
+    }
+
+    /// Hexes holding at least one unit, with the units in them.
+    pub fn occupied_hexes(&self) -> impl Iterator<Item = (Hex, &[Entity])> {
+        self.by_hex
+            .iter()
+            .map(|(&hex, units)| (hex, units.as_slice()))
+    }
+
+    pub fn position_of(&self, unit: Entity) -> Option<Hex> {
+        self.positions.get(&unit).copied()
+    }
+
+    fn insert(&mut self, unit: Entity, hex: Hex) {
+        match self.positions.insert(unit, hex) {
+            Some(previous) if previous == hex => return,
+            Some(previous) => self.remove_from_hex(unit, previous),
+            None => {}
+        }
+        self.by_hex.entry(hex).or_default().push(unit);
+    }
+
+    fn remove(&mut self, unit: Entity) {
+        if let Some(hex) = self.positions.remove(&unit) {
+            self.remove_from_hex(unit, hex);
+        }
+    }
+
+    fn remove_from_hex(&mut self, unit: Entity, hex: Hex) {
+        if let Some(units) = self.by_hex.get_mut(&hex) {
+            units.retain(|&u| u != unit);
+            if units.is_empty() {
+                self.by_hex.remove(&hex);
+            }
+        }
+    }
+}
+
+pub fn update_unit_spatial_index(
+    mut index: ResMut<UnitSpatialIndex>,
+    moved: Query<(Entity, &GamePosition), (With<Unit>, Changed<GamePosition>)>,
+    mut removed_units: RemovedComponents<Unit>,
+    mut removed_positions: RemovedComponents<GamePosition>,
+) {
+    for unit in removed_units.read().chain(removed_positions.read()) {
+        index.remove(unit);
+    }
+    for (unit, position) in moved.iter() {
+        index.insert(unit, position.hex);
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        reflect::ReflectResource,
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, SystemCondition, common_conditions::resource_changed},
+        system::{Commands, Query, Res, ResMut},
+    },
+    platform::collections::HashMap,
+    reflect::Reflect,
+};
+use hexx::Hex;
+
+use crate::{
+    movement::{MovemenetPenalty, MovementPenalties, MovementPenaltyReason},
+    units::{Convoy, Unit, UnitSpatialIndex, UnitTypeList, update_unit_spatial_index},
+};
+
+/// Multiplier on the time units in an overstacked hex need to cross a hex.
+const OVERSTACK_MOVEMENT_PENALTY: f32 = 1.5;
+
+pub struct StackingPlugin;
+
+impl Plugin for StackingPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<StackingConfig>()
+            .register_type::<Overstacked>()
+            .init_resource::<StackingConfig>()
+            .init_resource::<HexOccupancy>()
+            .add_systems(
+                Update,
+                (update_hex_occupancy, apply_overstack_penalties)
+                    .chain()
+                    .after(update_unit_spatial_index)
+                    .run_if(
+                        resource_changed::<UnitSpatialIndex>
+                            .or(resource_changed::<StackingConfig>)
+                            .or(resource_changed::<UnitTypeList>),
+                    ),
+            );
+    }
+}
+
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct StackingConfig {
+    /// Total stacking value of the units a single hex holds without penalties.
+    pub hex_limit: f32,
+}
+
+impl Default for StackingConfig {
+    fn default() -> Self {
+        Self { hex_limit: 6.0 }
+    }
+}
+
+/// Stacking value taken up in each occupied hex. Rebuilt from the [`UnitSpatialIndex`]
+/// whenever units move.
+#[derive(Resource, Debug, Default)]
+pub struct HexOccupancy {
+    limit: f32,
+    used: HashMap<Hex, f32>,
+    values: HashMap<Entity, f32>,
+}
+
+impl HexOccupancy {
+    pub fn used(&self, hex: Hex) -> f32 {
+        self.used.get(&hex).copied().unwrap_or(0.0)
+    }
+
+    /// Stacking value of a unit on the map, zero for units not known yet.
+    pub fn stacking_value_of(&self, unit: Entity) -> f32 {
+        self.values.get(&unit).copied().unwrap_or(0.0)
+    }
+
+    /// Whether a unit with given stacking value can end its move in the hex without
+    /// overstacking it.
+    pub fn has_room(&self, hex: Hex, stacking_value: f32) -> bool {
+        self.used(hex) + stacking_value <= self.limit
+    }
+
+    pub fn is_overstacked(&self, hex: Hex) -> bool {
+        self.used(hex) > self.limit
+    }
+}
+
+/// Present on every unit in a hex holding more than the stacking limit.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct Overstacked {
+    /// Stacking value above the limit in the unit's hex.
+    pub excess: f32,
+}
+
+fn update_hex_occupancy(
+    index: Res<UnitSpatialIndex>,
+    units: Query<(&Unit, Option<&Convoy>)>,
+    unit_types: Res<UnitTypeList>,
+    config: Res<StackingConfig>,
+    mut occupancy: ResMut<HexOccupancy>,
+) {
+    let type_value = |unit: &Unit| {
+        unit_types
+            .types
+            .get(&unit.unit_type)
+            .map_or(0.0, |details| details.stacking_value)
+    };
+    let mut used = HashMap::default();
+    let mut values = HashMap::default();
+    for (hex, occupants) in index.occupied_hexes() {
+        let mut total = 0.0;
+        for &entity in occupants {
+            let Ok((unit, convoy)) = units.get(entity) else {
+                continue;
+            };
+            // Convoys take up as much room as the vehicles they are made of.
+            let value = match convoy {
+                Some(convoy) => units
+                    .iter_many(&convoy.members)
+                    .map(|(member, _)| type_value(member))
+                    .sum(),
+                None => type_value(unit),
+            };
+            values.insert(entity, value);
+            total += value;
+        }
+        used.insert(hex, total);
+    }
+    *occupancy = HexOccupancy {
+        limit: config.hex_limit,
+        used,
+        values,
+    };
+}
+
+fn apply_overstack_penalties(
+    index: Res<UnitSpatialIndex>,
+    occupancy: Res<HexOccupancy>,
+    marked: Query<Entity, With<Overstacked>>,
+    mut penalties: Query<&mut MovementPenalties>,
+    mut commands: Commands,
+) {
+    // Units which left an overstacked hex, or the map altogether.
+    for entity in marked.iter() {
+        if index
+            .position_of(entity)
+            .is_some_and(|hex| occupancy.is_overstacked(hex))
+        {
+            continue;
+        }
+        commands.entity(entity).remove::<Overstacked>();
+        if let Ok(mut penalties) = penalties.get_mut(entity) {
+            penalties.remove(MovementPenaltyReason::Overstacked);
+        }
+    }
+    for (hex, occupants) in index.occupied_hexes() {
+        if !occupancy.is_overstacked(hex) {
+            continue;
+        }
+        let excess = occupancy.used(hex) - occupancy.limit;
+        for &entity in occupants {
+            commands.entity(entity).insert(Overstacked { excess });
+            if let Ok(mut penalties) = penalties.get_mut(entity) {
+                penalties.set(MovemenetPenalty {
+                    value: OVERSTACK_MOVEMENT_PENALTY,
+                    reason: MovementPenaltyReason::Overstacked,
+                });
+            }
+        }
+    }
+}
@@ -4,7 +4,10 @@ use bevy::{
     reflect::Reflect,
 };
 
-use crate::resources::{ResourceStack, VolumeInLitters, WeightInKilograms};
+use crate::resources::{
+    PalletizationConfig, ResourceAmout, ResourceStack, ResourceTypeId, ResourceTypes,
+    VolumeInLitters, WeightInKilograms,
+};
 
 pub struct SupplyPlugin;
 impl Plugin for SupplyPlugin {
@@ -19,3 +22,316 @@ pub struct SupplyStorage {
     pub max_weight: Option<WeightInKilograms>,
     pub max_volume: Option<VolumeInLitters>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SupplyError {
+    UnknownResourceType(ResourceTypeId),
+    /// Pallets of a resource type which cannot be palletized.
+    NotPalletizable(ResourceTypeId),
+}
+
+impl std::fmt::Display for SupplyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SupplyError::UnknownResourceType(id) => write!(f, "unknown resource type {id}"),
+            SupplyError::NotPalletizable(id) => {
+                write!(f, "resource type {id} cannot be palletized")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SupplyError {}
+
+/// Tolerance used when comparing stored amounts against capacity, to absorb float rounding.
+const CAPACITY_EPSILON: f32 = 1e-3;
+
+impl SupplyStorage {
+    pub fn new(max_weight: Option<WeightInKilograms>, max_volume: Option<VolumeInLitters>) -> Self {
+        Self {
+            storage: Vec::new(),
+            max_weight,
+            max_volume,
+        }
+    }
+
+    pub fn weight(&self, types: &ResourceTypes, config: &PalletizationConfig) -> WeightInKilograms {
+        self.storage
+            .iter()
+            .filter_map(|stack| footprint(stack, types, config).ok())
+            .map(|(weight, _)| weight)
+            .sum()
+    }
+
+    pub fn volume(&self, types: &ResourceTypes, config: &PalletizationConfig) -> VolumeInLitters {
+        self.storage
+            .iter()
+            .filter_map(|stack| footprint(stack, types, config).ok())
+            .map(|(_, volume)| volume)
+            .sum()
+    }
+
+    /// Stack of the given resource type and kind of amount held by this storage, if any.
+    pub fn get(
+        &self,
+        resource_type: ResourceTypeId,
+        kind: &ResourceAmout,
+    ) -> Option<&ResourceStack> {
+        self.storage
+            .iter()
+            .find(|stack| stack.resource_type == resource_type && stack.amount.same_kind(kind))
+    }
+
+    /// Largest part of `stack` that fits into the remaining capacity.
+    pub fn fitting(
+        &self,
+        stack: &ResourceStack,
+        types: &ResourceTypes,
+        config: &PalletizationConfig,
+    ) -> Result<ResourceStack, SupplyError> {
+        let resource_type = types
+            .get(stack.resource_type)
+            .ok_or(SupplyError::UnknownResourceType(stack.resource_type))?;
+        let (unit_weight, unit_volume) = resource_type
+            .footprint(&stack.amount, config)
+            .ok_or(SupplyError::NotPalletizable(stack.resource_type))?;
+        let mut count = stack.amount.count();
+        if let Some(max_weight) = self.max_weight
+            && unit_weight > 0.0
+        {
+            let free = (max_weight - self.weight(types, config) + CAPACITY_EPSILON).max(0.0);
+            count = count.min(free / unit_weight);
+        }
+        if let Some(max_volume) = self.max_volume
+            && unit_volume > 0.0
+        {
+            let free = (max_volume - self.volume(types, config) + CAPACITY_EPSILON).max(0.0);
+            count = count.min(free / unit_volume);
+        }
+        Ok(ResourceStack {
+            resource_type: stack.resource_type,
+            amount: stack.amount.with_count(count),
+        })
+    }
+
+    /// Adds as much of `stack` as fits, merging it with a stack of the same resource and kind.
+    /// Returns what was actually added.
+    pub fn add(
+        &mut self,
+        stack: ResourceStack,
+        types: &ResourceTypes,
+        config: &PalletizationConfig,
+    ) -> Result<ResourceStack, SupplyError> {
+        let added = self.fitting(&stack, types, config)?;
+        if !added.amount.is_empty() {
+            self.insert(added);
+        }
+        Ok(added)
+    }
+
+    /// Removes up to `stack` from the storage. Returns what was actually removed.
+    pub fn remove(&mut self, stack: ResourceStack) -> ResourceStack {
+        let Some(index) = self.storage.iter().position(|stored| {
+            stored.resource_type == stack.resource_type && stored.amount.same_kind(&stack.amount)
+        }) else {
+            return ResourceStack {
+                amount: stack.amount.with_count(0.0),
+                ..stack
+            };
+        };
+        let stored = &mut self.storage[index];
+        let count = stack.amount.count().min(stored.amount.count()).max(0.0);
+        let removed = stack.amount.with_count(count);
+        stored.amount = stored
+            .amount
+            .with_count(stored.amount.count() - removed.count());
+        if stored.amount.is_empty() {
+            self.storage.remove(index);
+        }
+        ResourceStack {
+            resource_type: stack.resource_type,
+            amount: removed,
+        }
+    }
+
+    /// Moves up to `stack` from this storage into `target`, limited by what this storage holds
+    /// and what `target` can take. Returns what was actually moved.
+    pub fn transfer(
+        &mut self,
+        target: &mut SupplyStorage,
+        stack: ResourceStack,
+        types: &ResourceTypes,
+        config: &PalletizationConfig,
+    ) -> Result<ResourceStack, SupplyError> {
+        let available = self
+            .get(stack.resource_type, &stack.amount)
+            .map_or(0.0, |stored| stored.amount.count());
+        let requested = ResourceStack {
+            resource_type: stack.resource_type,
+            amount: stack.amount.with_count(stack.amount.count().min(available)),
+        };
+        let fitting = target.fitting(&requested, types, config)?;
+        let moved = self.remove(fitting);
+        if !moved.amount.is_empty() {
+            target.insert(moved);
+        }
+        Ok(moved)
+    }
+
+    /// Adds a stack without checking capacity.
+    pub(crate) fn insert(&mut self, stack: ResourceStack) {
+        match self.storage.iter_mut().find(|stored| {
+            stored.resource_type == stack.resource_type && stored.amount.same_kind(&stack.amount)
+        }) {
+            Some(stored) => {
+                stored.amount = stored
+                    .amount
+                    .with_count(stored.amount.count() + stack.amount.count());
+            }
+            None => self.storage.push(stack),
+        }
+    }
+}
+
+fn footprint(
+    stack: &ResourceStack,
+    types: &ResourceTypes,
+    config: &PalletizationConfig,
+) -> Result<(WeightInKilograms, VolumeInLitters), SupplyError> {
+    let resource_type = types
+        .get(stack.resource_type)
+        .ok_or(SupplyError::UnknownResourceType(stack.resource_type))?;
+    let (weight, volume) = resource_type
+        .footprint(&stack.amount, config)
+        .ok_or(SupplyError::NotPalletizable(stack.resource_type))?;
+    let count = stack.amount.count();
+    Ok((weight * count, volume * count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::{PalletizationInfo, ResourceType};
+
+    const RATIONS: ResourceTypeId = 0;
+    const FUEL: ResourceTypeId = 1;
+
+    /// Rations come 100 to a pallet, fuel only loose.
+    fn types() -> ResourceTypes {
+        let rations = ResourceType {
+            key: "rations".into(),
+            unit_weight: 1.0,
+            unit_volume: 2.0,
+            palletization: PalletizationInfo::Palletized {
+                units_per_pallet: 100,
+            },
+            ..Default::default()
+        };
+        let fuel = ResourceType {
+            key: "fuel".into(),
+            unit_weight: 1.0,
+            unit_volume: 1.0,
+            ..Default::default()
+        };
+        ResourceTypes {
+            types: vec![rations, fuel],
+            ..Default::default()
+        }
+    }
+
+    fn config() -> PalletizationConfig {
+        PalletizationConfig {
+            pallete_volume: 50.0,
+        }
+    }
+
+    fn fluid(resource_type: ResourceTypeId, count: f32) -> ResourceStack {
+        ResourceStack {
+            resource_type,
+            amount: ResourceAmout::Fluid { count },
+        }
+    }
+
+    fn pallets(resource_type: ResourceTypeId, count: u32) -> ResourceStack {
+        ResourceStack {
+            resource_type,
+            amount: ResourceAmout::Pallets { count },
+        }
+    }
+
+    fn assert_count(stack: Option<&ResourceStack>, expected: f32) {
+        let count = stack.map_or(0.0, |stack| stack.amount.count());
+        assert!(
+            (count - expected).abs() < 0.01,
+            "expected {expected}, got {count}"
+        );
+    }
+
+    #[test]
+    fn transfer_fills_target_up_to_its_capacity() {
+        let (types, config) = (types(), config());
+        let mut source = SupplyStorage::new(None, None);
+        source.insert(fluid(FUEL, 80.0));
+        let mut target = SupplyStorage::new(Some(50.0), None);
+
+        let moved = source
+            .transfer(&mut target, fluid(FUEL, 80.0), &types, &config)
+            .unwrap();
+
+        assert_count(Some(&moved), 50.0);
+        assert_count(source.get(FUEL, &moved.amount), 30.0);
+        assert_count(target.get(FUEL, &moved.amount), 50.0);
+    }
+
+    #[test]
+    fn tighter_of_weight_and_volume_limits_applies() {
+        let (types, config) = (types(), config());
+        let by_volume = SupplyStorage::new(Some(1000.0), Some(100.0));
+        let by_weight = SupplyStorage::new(Some(30.0), Some(1000.0));
+
+        let volume_limited = by_volume.fitting(&fluid(RATIONS, 80.0), &types, &config);
+        let weight_limited = by_weight.fitting(&fluid(RATIONS, 80.0), &types, &config);
+
+        assert_count(volume_limited.ok().as_ref(), 50.0);
+        assert_count(weight_limited.ok().as_ref(), 30.0);
+    }
+
+    #[test]
+    fn only_whole_pallets_fit() {
+        let (types, config) = (types(), config());
+        // Room for two pallets of 250 litres each, but not for a third.
+        let storage = SupplyStorage::new(None, Some(600.0));
+
+        let fitting = storage.fitting(&pallets(RATIONS, 5), &types, &config);
+
+        assert_eq!(fitting, Ok(pallets(RATIONS, 2)));
+    }
+
+    #[test]
+    fn loose_and_palletized_stock_are_kept_apart() {
+        let (types, config) = (types(), config());
+        let mut storage = SupplyStorage::new(None, None);
+        storage.add(fluid(RATIONS, 10.0), &types, &config).unwrap();
+        storage.add(pallets(RATIONS, 1), &types, &config).unwrap();
+        storage.add(fluid(RATIONS, 5.0), &types, &config).unwrap();
+
+        let removed = storage.remove(fluid(RATIONS, 20.0));
+
+        assert_count(Some(&removed), 15.0);
+        assert_eq!(storage.storage, vec![pallets(RATIONS, 1)]);
+        assert_count(
+            storage.get(RATIONS, &ResourceAmout::Fluid { count: 0.0 }),
+            0.0,
+        );
+    }
+
+    #[test]
+    fn fuel_cannot_be_palletized() {
+        let (types, config) = (types(), config());
+        let storage = SupplyStorage::new(None, None);
+
+        let fitting = storage.fitting(&pallets(FUEL, 1), &types, &config);
+
+        assert_eq!(fitting, Err(SupplyError::NotPalletizable(FUEL)));
+    }
+}
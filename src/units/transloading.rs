@@ -0,0 +1,200 @@
+use bevy::{
+    app::{App, FixedUpdate, Plugin},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        message::{Message, MessageWriter},
+        system::{Commands, Query, Res},
+    },
+    log::{debug, warn},
+    reflect::Reflect,
+    time::{Fixed, Time},
+};
+
+use crate::{
+    movement::GamePosition,
+    resources::{
+        PalletizationConfig, PalletizationInfo, ResourceAmout, ResourceStack, ResourceTypeId,
+        ResourceTypes,
+    },
+    time::game_delta,
+    units::{SupplyStorage, Unit, UnitTypeList},
+};
+
+pub struct TransloadingPlugin;
+
+impl Plugin for TransloadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Transloading>()
+            .add_message::<TransloadingProgressMessage>()
+            .add_message::<TransloadingCompletedMessage>()
+            .add_systems(FixedUpdate, transload);
+    }
+}
+
+/// Assigns a unit to break down pallets held by `storage` into fluid stock. The unit has to be
+/// in the same hex as the storage and its type needs a transloading rate.
+#[derive(Component, Debug, Reflect)]
+pub struct Transloading {
+    pub storage: Entity,
+    /// Fraction of the pallet currently being broken down.
+    pub progress: f32,
+}
+
+impl Transloading {
+    pub fn new(storage: Entity) -> Self {
+        Self {
+            storage,
+            progress: 0.0,
+        }
+    }
+}
+
+/// Sent every time a pallet has been broken down.
+#[derive(Message, Debug)]
+pub struct TransloadingProgressMessage {
+    pub unit: Entity,
+    pub storage: Entity,
+    pub resource_type: ResourceTypeId,
+    pub remaining_pallets: u32,
+}
+
+/// Why a unit stopped transloading before the storage ran out of pallets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransloadingError {
+    /// The storage no longer exists.
+    StorageLost,
+    /// The unit's type has no transloading rate.
+    NotCapable,
+    /// The unit is not in the storage's hex.
+    NotInSameHex,
+    /// The storage has no room for the contents of the next pallet.
+    NoRoom,
+}
+
+/// Sent when a storage has no more pallets to break down, or with an error when the unit
+/// stopped transloading early.
+#[derive(Message, Debug)]
+pub struct TransloadingCompletedMessage {
+    pub unit: Entity,
+    pub storage: Entity,
+    pub result: Result<(), TransloadingError>,
+}
+
+fn transload(
+    mut workers: Query<(Entity, &Unit, &GamePosition, &mut Transloading)>,
+    mut storages: Query<(&mut SupplyStorage, Option<&GamePosition>)>,
+    unit_types: Res<UnitTypeList>,
+    resource_types: Res<ResourceTypes>,
+    config: Res<PalletizationConfig>,
+    time: Res<Time<Fixed>>,
+    mut progress_messages: MessageWriter<TransloadingProgressMessage>,
+    mut completed_messages: MessageWriter<TransloadingCompletedMessage>,
+    mut commands: Commands,
+) {
+    let elapsed_hours = game_delta(&time).as_secs_f32() / 3600.0;
+    for (unit, unit_details, position, mut transloading) in workers.iter_mut() {
+        let storage_entity = transloading.storage;
+        let rate = unit_types
+            .types
+            .get(&unit_details.unit_type)
+            .and_then(|details| details.transloading_rate);
+        let mut finish = |result: Result<(), TransloadingError>| {
+            match result {
+                Ok(()) => debug!("Unit {unit:?} finished transloading at {storage_entity:?}"),
+                Err(error) => {
+                    warn!("Unit {unit:?} stopped transloading at {storage_entity:?}: {error:?}")
+                }
+            }
+            commands.entity(unit).remove::<Transloading>();
+            completed_messages.write(TransloadingCompletedMessage {
+                unit,
+                storage: storage_entity,
+                result,
+            });
+        };
+        let Some(rate) = rate else {
+            finish(Err(TransloadingError::NotCapable));
+            continue;
+        };
+        let Ok((mut storage, storage_position)) = storages.get_mut(storage_entity) else {
+            finish(Err(TransloadingError::StorageLost));
+            continue;
+        };
+        if storage_position.is_some_and(|storage_position| storage_position.hex != position.hex) {
+            finish(Err(TransloadingError::NotInSameHex));
+            continue;
+        }
+
+        transloading.progress += rate * elapsed_hours;
+        let mut no_room = false;
+        while transloading.progress >= 1.0 {
+            let Some((resource_type, units_per_pallet)) = next_pallet(&storage, &resource_types)
+            else {
+                break;
+            };
+            let pallet = ResourceStack {
+                resource_type,
+                amount: ResourceAmout::Pallets { count: 1 },
+            };
+            storage.remove(pallet);
+            let contents = ResourceStack {
+                resource_type,
+                amount: ResourceAmout::Fluid {
+                    count: units_per_pallet as f32,
+                },
+            };
+            let fits = storage
+                .fitting(&contents, &resource_types, &config)
+                .is_ok_and(|fitting| fitting == contents);
+            if !fits {
+                storage.insert(pallet);
+                no_room = true;
+                break;
+            }
+            let _ = storage.add(contents, &resource_types, &config);
+            transloading.progress -= 1.0;
+            progress_messages.write(TransloadingProgressMessage {
+                unit,
+                storage: storage_entity,
+                resource_type,
+                remaining_pallets: remaining_pallets(&storage),
+            });
+        }
+
+        if no_room {
+            finish(Err(TransloadingError::NoRoom));
+        } else if next_pallet(&storage, &resource_types).is_none() {
+            finish(Ok(()));
+        }
+    }
+}
+
+/// Resource type and pallet size of the first pallet stack in the storage.
+fn next_pallet(
+    storage: &SupplyStorage,
+    resource_types: &ResourceTypes,
+) -> Option<(ResourceTypeId, u32)> {
+    storage.storage.iter().find_map(|stack| {
+        let ResourceAmout::Pallets { count } = stack.amount else {
+            return None;
+        };
+        let PalletizationInfo::Palletized { units_per_pallet } =
+            resource_types.get(stack.resource_type)?.palletization
+        else {
+            return None;
+        };
+        (count > 0).then_some((stack.resource_type, units_per_pallet))
+    })
+}
+
+fn remaining_pallets(storage: &SupplyStorage) -> u32 {
+    storage
+        .storage
+        .iter()
+        .map(|stack| match stack.amount {
+            ResourceAmout::Pallets { count } => count,
+            ResourceAmout::Fluid { .. } => 0,
+        })
+        .sum()
+}
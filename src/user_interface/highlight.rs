@@ -0,0 +1,152 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::{Assets, Handle},
+    camera::{Camera, visibility::Visibility},
+    color::Color,
+    ecs::{
+        change_detection::DetectChangesMut,
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::With,
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, common_conditions::resource_changed},
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    math::primitives::RegularPolygon,
+    mesh::{Mesh, Mesh2d},
+    prelude::Name,
+    reflect::Reflect,
+    sprite_render::{ColorMaterial, MeshMaterial2d},
+    transform::components::{GlobalTransform, Transform},
+    window::{PrimaryWindow, Window},
+};
+
+use crate::{
+    map::{HexGrid, SPRITE_SIZE},
+    movement::GamePosition,
+    unit_managment::SelectedUnitList,
+    units::{Subordinates, atomic_units},
+    user_interface::cursor_world_position,
+};
+
+const SELECTION_COLOR: Color = Color::srgba(0.4, 1.0, 0.4, 0.6);
+const HOVER_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.25);
+/// Hex tiles are drawn at z 0 and units at z 1, highlights go in between.
+const HOVER_Z: f32 = 0.25;
+/// Relative to the unit the indicator is attached to.
+const SELECTION_INDICATOR_Z: f32 = -0.5;
+
+/// Overlays are separate entities rather than a tint of the hex sprites, which daylight
+/// already uses.
+pub struct HighlightPlugin;
+
+impl Plugin for HighlightPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SelectionIndicator>()
+            .register_type::<HoverHighlight>()
+            .add_systems(Startup, setup_highlights)
+            .add_systems(
+                Update,
+                (
+                    update_selection_indicators.run_if(resource_changed::<SelectedUnitList>),
+                    update_hover_highlight,
+                ),
+            );
+    }
+}
+
+/// Hex underlay shown below a selected unit. Spawned as a child of the unit.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+struct SelectionIndicator;
+
+/// Hex outline following the cursor.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+struct HoverHighlight;
+
+#[derive(Resource)]
+struct HighlightAssets {
+    hex: Handle<Mesh>,
+    selection: Handle<ColorMaterial>,
+}
+
+fn setup_highlights(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    // Pointy hexes, matching the grid layout.
+    let hex = meshes.add(RegularPolygon::new(SPRITE_SIZE.y / 2.0, 6));
+    commands.spawn((
+        Name::new("Hover Highlight"),
+        HoverHighlight,
+        Mesh2d(hex.clone()),
+        MeshMaterial2d(materials.add(HOVER_COLOR)),
+        Transform::from_xyz(0.0, 0.0, HOVER_Z),
+        Visibility::Hidden,
+    ));
+    commands.insert_resource(HighlightAssets {
+        hex,
+        selection: materials.add(SELECTION_COLOR),
+    });
+}
+
+fn update_selection_indicators(
+    selected_units: Res<SelectedUnitList>,
+    assets: Res<HighlightAssets>,
+    indicators: Query<(Entity, &ChildOf), With<SelectionIndicator>>,
+    subordinates: Query<&Subordinates>,
+    units: Query<(), With<GamePosition>>,
+    mut commands: Commands,
+) {
+    // Composite units are not on the map, their atomic units are marked instead.
+    let highlighted: Vec<Entity> = selected_units
+        .units()
+        .iter()
+        .flat_map(|&unit| atomic_units(unit, &subordinates))
+        .filter(|&unit| units.contains(unit))
+        .collect();
+    let mut marked = Vec::new();
+    for (indicator, child_of) in indicators.iter() {
+        if highlighted.contains(&child_of.parent()) {
+            marked.push(child_of.parent());
+        } else {
+            commands.entity(indicator).despawn();
+        }
+    }
+    for unit in highlighted {
+        if marked.contains(&unit) {
+            continue;
+        }
+        commands.entity(unit).with_child((
+            SelectionIndicator,
+            Mesh2d(assets.hex.clone()),
+            MeshMaterial2d(assets.selection.clone()),
+            Transform::from_xyz(0.0, 0.0, SELECTION_INDICATOR_Z),
+        ));
+    }
+}
+
+fn update_hover_highlight(
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    grid: Option<Res<HexGrid>>,
+    mut highlight: Single<(&mut Transform, &mut Visibility), With<HoverHighlight>>,
+) {
+    let (transform, visibility) = &mut *highlight;
+    let hovered = grid
+        .zip(cursor_world_position(&window, &camera))
+        .and_then(|(grid, pos)| {
+            let hex = grid.to_hex_coordinates(pos);
+            grid.entity_at(hex).map(|_| grid.to_global_coordinates(hex))
+        });
+    match hovered {
+        Some(center) => {
+            transform.translation = center.extend(HOVER_Z);
+            visibility.set_if_neq(Visibility::Inherited);
+        }
+        None => {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+    }
+}
@@ -1,17 +1,34 @@
+mod highlight;
 mod hud;
+mod route;
+mod selection;
 mod theme;
 mod unit_list;
 use bevy::{
-    app::{App, Plugin, Update}, camera::Camera, ecs::{
-        entity::Entity, event::EventWriter, message::MessageWriter, query::With, schedule::IntoScheduleConfigs, system::{Query, Res, Single}
-    }, input::{common_conditions::input_just_pressed, mouse::MouseButton}, log::debug, math::Vec2, transform::components::{GlobalTransform, Transform}, window::{PrimaryWindow, Window}
+    app::{App, Plugin, Update},
+    camera::Camera,
+    ecs::{
+        message::MessageWriter,
+        query::With,
+        schedule::IntoScheduleConfigs,
+        system::{Query, Res, Single},
+    },
+    input::{common_conditions::input_just_pressed, mouse::MouseButton},
+    math::Vec2,
+    transform::components::GlobalTransform,
+    window::{PrimaryWindow, Window},
 };
 
+use leafwing_input_manager::prelude::ActionState;
+
 use crate::{
+    game_actions::GameActions,
     map::HexGrid,
-    unit_managment::{SelectUnitMessage, orders::MoveOrderIssuedMessage},
-    units::Unit,
-    user_interface::{hud::HudPlugin, theme::ThemePlugin, unit_list::UnitListPlugin},
+    unit_managment::orders::MoveOrderIssuedMessage,
+    user_interface::{
+        highlight::HighlightPlugin, hud::HudPlugin, route::RoutePlugin,
+        selection::SelectionInputPlugin, theme::ThemePlugin, unit_list::UnitListPlugin,
+    },
 };
 
 pub struct UserInterfacePlugin;
@@ -20,51 +37,41 @@ impl Plugin for UserInterfacePlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (
-                mouse_left_click.run_if(input_just_pressed(MouseButton::Left)),
-                mouse_right_click.run_if(input_just_pressed(MouseButton::Right)),
-            ),
+            mouse_right_click.run_if(input_just_pressed(MouseButton::Right)),
         )
-        .add_plugins((UnitListPlugin, ThemePlugin, HudPlugin));
+        .add_plugins((
+            UnitListPlugin,
+            ThemePlugin,
+            HudPlugin,
+            SelectionInputPlugin,
+            HighlightPlugin,
+            RoutePlugin,
+        ));
     }
 }
 
+/// World position under the mouse cursor, if the cursor is inside the window.
+fn cursor_world_position(
+    window: &Window,
+    camera: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let (camera, camera_transform) = camera.single().ok()?;
+    window
+        .cursor_position()
+        .and_then(|pos| camera.viewport_to_world_2d(camera_transform, pos).ok())
+}
+
 fn mouse_right_click(
     window: Single<&Window, With<PrimaryWindow>>,
+    input: Single<&ActionState<GameActions>>,
     mut writer: MessageWriter<MoveOrderIssuedMessage>,
     map: Res<HexGrid>,
     camera: Query<(&Camera, &GlobalTransform)>,
 ) {
-    let (camera, camera_transform) = camera.single().unwrap();
-    if let Some(cursor_pos) = window
-        .cursor_position()
-        .and_then(|pos| camera.viewport_to_world_2d(camera_transform, pos).ok())
-    {
+    if let Some(cursor_pos) = cursor_world_position(&window, &camera) {
         writer.write(MoveOrderIssuedMessage {
             destination: map.to_hex_coordinates(cursor_pos),
+            queued: input.pressed(&GameActions::QueueOrder),
         });
     }
 }
-
-fn mouse_left_click(
-    window: Single<&Window, With<PrimaryWindow>>,
-    mut writer: EventWriter<SelectUnitMessage>,
-    camera: Query<(&Camera, &GlobalTransform)>,
-    units: Query<(Entity, &Transform), With<Unit>>,
-) {
-    let (camera, camera_transform) = camera.single().unwrap();
-    if let Some(cursor_pos) = window
-        .cursor_position()
-        .and_then(|pos| camera.viewport_to_world_2d(camera_transform, pos).ok())
-    {
-        for (entity, transform) in units.iter() {
-            let distance =
-                cursor_pos.distance(Vec2::new(transform.translation.x, transform.translation.y));
-            if distance < 30.0 {
-                writer.write(SelectUnitMessage { unit: entity });
-                debug!(name: "unit_management:selection", "Selected unit: {:?}", entity);
-                break;
-            }
-        }
-    }
-}
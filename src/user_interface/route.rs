@@ -0,0 +1,274 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    camera::{Camera, visibility::Visibility},
+    color::Color,
+    ecs::{
+        change_detection::DetectChangesMut,
+        component::Component,
+        entity::Entity,
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    gizmos::gizmos::Gizmos,
+    math::Vec2,
+    prelude::Name,
+    sprite::Text2d,
+    text::TextFont,
+    time::{Real, Time},
+    transform::components::{GlobalTransform, Transform},
+    utils::default,
+    window::{PrimaryWindow, Window},
+};
+use hexx::Hex;
+
+use crate::{
+    map::HexGrid,
+    movement::{
+        DifficultTerrain, GamePosition, MovementConfig, MovementPenalties, MovementStats,
+        MovingTowards, Path, SpeedLimit,
+    },
+    time::CurrentTimePoint,
+    unit_managment::{
+        SelectedUnitList,
+        orders::{Order, OrderQueue},
+    },
+    units::{HexOccupancy, Subordinates, Unit, UnitTypeList, atomic_units},
+    user_interface::cursor_world_position,
+};
+
+const ROUTE_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+const QUEUED_ROUTE_COLOR: Color = Color::srgba(1.0, 0.85, 0.2, 0.5);
+const PREVIEW_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.6);
+const DESTINATION_MARKER_RADIUS: f32 = 4.0;
+/// Above units, so the label is not hidden by a stack in the destination hex.
+const ETA_LABEL_Z: f32 = 5.0;
+const ETA_LABEL_OFFSET: Vec2 = Vec2::new(0.0, 16.0);
+/// Each recomputation searches a route for every selected unit, so sweeping the cursor over
+/// the map should not trigger one per frame.
+const PREVIEW_REFRESH_INTERVAL: Duration = Duration::from_millis(150);
+
+pub struct RoutePlugin;
+
+impl Plugin for RoutePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RoutePreview>()
+            .add_systems(Startup, setup_eta_label)
+            .add_systems(
+                Update,
+                (
+                    draw_selected_routes,
+                    draw_queued_orders,
+                    (update_route_preview, draw_route_preview, update_eta_label).chain(),
+                ),
+            );
+    }
+}
+
+/// Routes the selected units would take to the hovered hex, recomputed only when the hovered
+/// hex, the selection or the units' hexes change, and at most every
+/// [`PREVIEW_REFRESH_INTERVAL`].
+#[derive(Resource, Debug, Default)]
+struct RoutePreview {
+    destination: Option<Hex>,
+    selection: Vec<Entity>,
+    /// Atomic units of the selection and the hexes their routes start from.
+    starts: Vec<(Entity, Hex)>,
+    /// Real time of the last recomputation.
+    updated: Duration,
+    routes: Vec<Vec<Hex>>,
+    /// Time the slowest unit needs to reach the destination.
+    travel_time: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+struct EtaLabel;
+
+fn setup_eta_label(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Route ETA"),
+        EtaLabel,
+        Text2d::default(),
+        TextFont {
+            font_size: 10.0,
+            ..default()
+        },
+        Transform::from_xyz(0.0, 0.0, ETA_LABEL_Z),
+        Visibility::Hidden,
+    ));
+}
+
+fn draw_selected_routes(
+    selected_units: Res<SelectedUnitList>,
+    units: Query<(&Transform, &MovingTowards, &Path)>,
+    subordinates: Query<&Subordinates>,
+    grid: Res<HexGrid>,
+    mut gizmos: Gizmos,
+) {
+    let selected = selected_units
+        .units()
+        .iter()
+        .flat_map(|&unit| atomic_units(unit, &subordinates));
+    for unit in selected {
+        let Ok((transform, moving, path)) = units.get(unit) else {
+            continue;
+        };
+        let remaining = path
+            .waypoints
+            .iter()
+            .skip_while(|&&hex| hex != moving.destination)
+            .map(|&hex| grid.to_global_coordinates(hex));
+        let points: Vec<Vec2> = std::iter::once(transform.translation.truncate())
+            .chain(remaining)
+            .collect();
+        gizmos.linestrip_2d(points.iter().copied(), ROUTE_COLOR);
+        if let Some(&last) = points.last() {
+            gizmos.circle_2d(last, DESTINATION_MARKER_RADIUS, ROUTE_COLOR);
+        }
+    }
+}
+
+/// Straight lines between the destinations of queued move orders, starting from where the
+/// current order ends.
+fn draw_queued_orders(
+    selected_units: Res<SelectedUnitList>,
+    units: Query<(&GamePosition, &OrderQueue)>,
+    subordinates: Query<&Subordinates>,
+    grid: Res<HexGrid>,
+    mut gizmos: Gizmos,
+) {
+    let selected = selected_units
+        .units()
+        .iter()
+        .flat_map(|&unit| atomic_units(unit, &subordinates));
+    for unit in selected {
+        let Ok((position, queue)) = units.get(unit) else {
+            continue;
+        };
+        let start = queue
+            .active()
+            .and_then(|(_, order)| order.destination())
+            .unwrap_or(position.hex);
+        let destinations = queue.pending().filter_map(Order::destination);
+        let points: Vec<Vec2> = std::iter::once(start)
+            .chain(destinations)
+            .map(|hex| grid.to_global_coordinates(hex))
+            .collect();
+        if points.len() < 2 {
+            continue;
+        }
+        gizmos.linestrip_2d(points.iter().copied(), QUEUED_ROUTE_COLOR);
+        for &point in points.iter().skip(1) {
+            gizmos.circle_2d(point, DESTINATION_MARKER_RADIUS, QUEUED_ROUTE_COLOR);
+        }
+    }
+}
+
+fn update_route_preview(
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    selected_units: Res<SelectedUnitList>,
+    units: Query<(
+        &Unit,
+        &GamePosition,
+        &MovementConfig,
+        Option<&MovementPenalties>,
+        Option<&MovementStats>,
+        Option<&SpeedLimit>,
+    )>,
+    subordinates: Query<&Subordinates>,
+    terrain: Query<&DifficultTerrain>,
+    unit_types: Res<UnitTypeList>,
+    occupancy: Res<HexOccupancy>,
+    grid: Res<HexGrid>,
+    time: Res<Time<Real>>,
+    mut preview: ResMut<RoutePreview>,
+) {
+    let destination = cursor_world_position(&window, &camera)
+        .map(|pos| grid.to_hex_coordinates(pos))
+        .filter(|&hex| grid.entity_at(hex).is_some() && !selected_units.is_empty());
+    let starts: Vec<(Entity, Hex)> = selected_units
+        .units()
+        .iter()
+        .flat_map(|&unit| atomic_units(unit, &subordinates))
+        .filter_map(|entity| Some((entity, units.get(entity).ok()?.1.hex)))
+        .collect();
+    if preview.destination == destination
+        && preview.selection == selected_units.units()
+        && preview.starts == starts
+    {
+        return;
+    }
+    if time.elapsed() < preview.updated + PREVIEW_REFRESH_INTERVAL {
+        return;
+    }
+    preview.destination = destination;
+    preview.selection = selected_units.units().to_vec();
+    preview.starts.clone_from(&starts);
+    preview.updated = time.elapsed();
+    preview.routes.clear();
+    preview.travel_time = None;
+    let Some(destination) = destination else {
+        return;
+    };
+
+    for &(entity, start) in starts.iter() {
+        let Ok((unit, _, config, penalties, own_stats, speed_limit)) = units.get(entity) else {
+            continue;
+        };
+        let Some(stats) = own_stats.or_else(|| unit_types.movement_stats(unit)) else {
+            continue;
+        };
+        let Ok(route) = grid.find_path(
+            start,
+            destination,
+            stats,
+            &terrain,
+            &occupancy,
+            occupancy.stacking_value_of(entity),
+        ) else {
+            continue;
+        };
+        let travel_time = grid.travel_time(
+            &route,
+            stats,
+            config.mode,
+            penalties.map_or(1.0, MovementPenalties::total),
+            speed_limit.map(|limit| limit.kph),
+            &terrain,
+        );
+        if let Some(travel_time) = travel_time {
+            preview.travel_time = preview.travel_time.max(Some(travel_time));
+        }
+        preview.routes.push(route);
+    }
+}
+
+fn draw_route_preview(preview: Res<RoutePreview>, grid: Res<HexGrid>, mut gizmos: Gizmos) {
+    for route in preview.routes.iter() {
+        gizmos.linestrip_2d(
+            route.iter().map(|&hex| grid.to_global_coordinates(hex)),
+            PREVIEW_COLOR,
+        );
+    }
+}
+
+fn update_eta_label(
+    preview: Res<RoutePreview>,
+    current_time: Res<CurrentTimePoint>,
+    grid: Res<HexGrid>,
+    mut label: Single<(&mut Text2d, &mut Transform, &mut Visibility), With<EtaLabel>>,
+) {
+    let (text, transform, visibility) = &mut *label;
+    let (Some(destination), Some(travel_time)) = (preview.destination, preview.travel_time) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    let position = grid.to_global_coordinates(destination) + ETA_LABEL_OFFSET;
+    transform.translation = position.extend(ETA_LABEL_Z);
+    text.0 = format!("ETA {}", current_time.0 + travel_time);
+    visibility.set_if_neq(Visibility::Inherited);
+}
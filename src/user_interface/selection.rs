@@ -0,0 +1,145 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    camera::Camera,
+    color::Color,
+    ecs::{
+        entity::Entity,
+        message::MessageWriter,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Query, Res, ResMut, Single},
+    },
+    gizmos::gizmos::Gizmos,
+    log::debug,
+    math::{Rect, Vec2},
+    transform::components::{GlobalTransform, Transform},
+    window::{PrimaryWindow, Window},
+};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    game_actions::GameActions,
+    map::HexGrid,
+    unit_managment::{SelectUnitMessage, SelectionMode},
+    units::{ConvoyMember, Unit, UnitSpatialIndex, update_unit_spatial_index},
+    user_interface::cursor_world_position,
+};
+
+/// Cursor movement, in pixels, below which a press and release is treated as a click.
+const DRAG_THRESHOLD: f32 = 5.0;
+const SELECTION_BOX_COLOR: Color = Color::srgb(0.4, 1.0, 0.4);
+
+pub struct SelectionInputPlugin;
+
+impl Plugin for SelectionInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectionDrag>().add_systems(
+            Update,
+            (
+                start_selection,
+                draw_selection_box,
+                finish_selection.after(update_unit_spatial_index),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Where the current left button press started, in screen and world space.
+#[derive(Resource, Default)]
+struct SelectionDrag {
+    start: Option<(Vec2, Vec2)>,
+}
+
+fn selection_mode(input: &ActionState<GameActions>) -> SelectionMode {
+    if input.pressed(&GameActions::ToggleSelection) {
+        SelectionMode::Toggle
+    } else if input.pressed(&GameActions::AddToSelection) {
+        SelectionMode::Add
+    } else {
+        SelectionMode::Replace
+    }
+}
+
+fn start_selection(
+    input: Single<&ActionState<GameActions>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    mut drag: ResMut<SelectionDrag>,
+) {
+    if !input.just_pressed(&GameActions::Select) {
+        return;
+    }
+    drag.start = window
+        .cursor_position()
+        .zip(cursor_world_position(&window, &camera));
+}
+
+fn draw_selection_box(
+    drag: Res<SelectionDrag>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    let Some((start_screen, start_world)) = drag.start else {
+        return;
+    };
+    let (Some(screen), Some(world)) = (
+        window.cursor_position(),
+        cursor_world_position(&window, &camera),
+    ) else {
+        return;
+    };
+    if start_screen.distance(screen) < DRAG_THRESHOLD {
+        return;
+    }
+    let rect = Rect::from_corners(start_world, world);
+    gizmos.rect_2d(rect.center(), rect.size(), SELECTION_BOX_COLOR);
+}
+
+fn finish_selection(
+    input: Single<&ActionState<GameActions>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    units: Query<(Entity, &Transform), (With<Unit>, Without<ConvoyMember>)>,
+    index: Res<UnitSpatialIndex>,
+    grid: Res<HexGrid>,
+    mut drag: ResMut<SelectionDrag>,
+    mut writer: MessageWriter<SelectUnitMessage>,
+) {
+    if !input.just_released(&GameActions::Select) {
+        return;
+    }
+    let Some((start_screen, start_world)) = drag.start.take() else {
+        return;
+    };
+    let (Some(screen), Some(world)) = (
+        window.cursor_position(),
+        cursor_world_position(&window, &camera),
+    ) else {
+        return;
+    };
+    let mode = selection_mode(&input);
+
+    let picked: Vec<Entity> = if start_screen.distance(screen) < DRAG_THRESHOLD {
+        // A click picks the whole stack in the hex.
+        index.units_at(grid.to_hex_coordinates(world)).to_vec()
+    } else {
+        let rect = Rect::from_corners(start_world, world);
+        units
+            .iter()
+            .filter(|(_, transform)| rect.contains(transform.translation.truncate()))
+            .map(|(entity, _)| entity)
+            .collect()
+    };
+    debug!(name: "unit_management:selection", "Selected units: {:?} ({:?})", picked, mode);
+    // Clicking empty ground only clears the selection when no modifier is held.
+    if picked.is_empty() && mode != SelectionMode::Replace {
+        return;
+    }
+    writer.write(SelectUnitMessage {
+        units: picked,
+        mode,
+    });
+}
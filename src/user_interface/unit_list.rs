@@ -1,7 +1,7 @@
 use bevy::{
     asset::AssetServer,
     ecs::{
-        schedule::{IntoScheduleConfigs, common_conditions},
+        schedule::{IntoScheduleConfigs, common_conditions::resource_changed},
         system::{Commands, Res},
     },
     prelude::*,
@@ -11,7 +11,7 @@ use bevy_hui::prelude::{HtmlComponents, HtmlFunctions, HtmlNode, TemplatePropert
 
 use crate::{
     camera::CameraSetup,
-    unit_managment::SelectUnitMessage,
+    unit_managment::SelectedUnitList,
     units::{self, Unit},
 };
 
@@ -20,7 +20,13 @@ pub struct UnitListPlugin;
 impl Plugin for UnitListPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_unit_list.after(CameraSetup))
-            .add_systems(Update, (on_unit_selected, setup_unit_list_element));
+            .add_systems(
+                Update,
+                (
+                    on_selection_changed.run_if(resource_changed::<SelectedUnitList>),
+                    setup_unit_list_element,
+                ),
+            );
     }
 }
 
@@ -60,21 +66,26 @@ fn setup_unit_list(
     );
 }
 
-fn on_unit_selected(
-    mut events: EventReader<SelectUnitMessage>,
+/// Rebuilds the list so it shows exactly the currently selected units.
+fn on_selection_changed(
+    selected_units: Res<SelectedUnitList>,
     list_ui: Single<Entity, With<UnitListSlotMarker>>,
+    elements: Query<Entity, With<UnitListElementComponent>>,
     mut commands: Commands,
     server: Res<AssetServer>,
 ) {
-    for event in events.read() {
-        commands.entity(*list_ui).with_children(|parent| {
+    for element in elements.iter() {
+        commands.entity(element).despawn();
+    }
+    commands.entity(*list_ui).with_children(|parent| {
+        for &unit in selected_units.units() {
             parent.spawn((
                 HtmlNode(server.load("ui/templates/hud/unit_list/unit_list_element.html")),
-                UnitListElementComponent { unit: event.unit },
+                UnitListElementComponent { unit },
                 TemplateProperties::default().with("action", SELECT_UNIT_ELEMENT_FN),
             ));
-        });
-    }
+        }
+    });
 }
 
 fn setup_unit_list_element(